@@ -0,0 +1,195 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use raycaster::{Camera, FrameBuffer, Level, Point, Rotation, TextureRegistry};
+
+const USAGE: &str = "Usage: frame_renderer [options] <output.png>
+
+Options:
+    --level <path>                  Level file to render (default: the built-in demo level)
+    --atlas <path>                  Texture atlas from texture_parser to make available to --level
+    --x <f32>                       Camera x position (default: level spawn)
+    --y <f32>                       Camera y position (default: level spawn)
+    --rotation <f32>                Camera rotation in degrees (default: level spawn)
+    --fov <u32>                     Field of view in degrees (default: 90)
+    --columns-per-ray <u32>         Screen columns sharing one ray, 1 to 16 (default: 1)
+    --fish-eye-correction           Enable fisheye correction
+    --pitch <f32>                   Degrees to look up (positive) or down, -30 to 30 (default: 0)
+    --eye-height <f32>              Camera height above the floor (default: 0.5)
+    --time <f32>                    Seconds since the level started, for animated textures (default: 0)
+    --width <usize>                 Output width in pixels (default: 1280)
+    --height <usize>                Output height in pixels (default: 720)
+    --no-minimap                    Don't draw the minimap";
+
+struct Options {
+    level_path: Option<String>,
+    atlas_path: Option<String>,
+    x: Option<f32>,
+    y: Option<f32>,
+    rotation: Option<f32>,
+    fov: u32,
+    columns_per_ray: u32,
+    fish_eye_correction: bool,
+    pitch: f32,
+    eye_height: f32,
+    time: f32,
+    width: usize,
+    height: usize,
+    minimap: bool,
+    output: String,
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {}", flag))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {}: {:?}", flag, value))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut level_path: Option<String> = None;
+    let mut atlas_path: Option<String> = None;
+    let (mut x, mut y, mut rotation): (Option<f32>, Option<f32>, Option<f32>) = (None, None, None);
+    let mut fov: u32 = 90;
+    let mut columns_per_ray: u32 = 1;
+    let mut fish_eye_correction = false;
+    let mut pitch: f32 = 0.0;
+    let mut eye_height: f32 = 0.5;
+    let mut time: f32 = 0.0;
+    let mut width: usize = 1280;
+    let mut height: usize = 720;
+    let mut minimap = true;
+    let mut output: Option<String> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--level" => level_path = Some(parse_value(&arg, args.next())?),
+            "--atlas" => atlas_path = Some(parse_value(&arg, args.next())?),
+            "--x" => x = Some(parse_value(&arg, args.next())?),
+            "--y" => y = Some(parse_value(&arg, args.next())?),
+            "--rotation" => rotation = Some(parse_value(&arg, args.next())?),
+            "--fov" => fov = parse_value::<u32>(&arg, args.next())?.clamp(1, 179),
+            "--columns-per-ray" => columns_per_ray = parse_value::<u32>(&arg, args.next())?.clamp(1, 16),
+            "--fish-eye-correction" => fish_eye_correction = true,
+            "--pitch" => pitch = parse_value::<f32>(&arg, args.next())?.clamp(-30.0, 30.0),
+            "--eye-height" => eye_height = parse_value(&arg, args.next())?,
+            "--time" => time = parse_value(&arg, args.next())?,
+            "--width" => width = parse_value(&arg, args.next())?,
+            "--height" => height = parse_value(&arg, args.next())?,
+            "--no-minimap" => minimap = false,
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg)),
+            _ if output.is_none() => output = Some(arg),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+
+    if width == 0 || height == 0 {
+        return Err("Width and height must be greater than 0".to_string());
+    }
+
+    Ok(Options {
+        level_path,
+        atlas_path,
+        x,
+        y,
+        rotation,
+        fov,
+        columns_per_ray,
+        fish_eye_correction,
+        pitch,
+        eye_height,
+        time,
+        width,
+        height,
+        minimap,
+        output: output.ok_or("Missing output path")?,
+    })
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{}\n", e);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let mut textures = TextureRegistry::builtin();
+    if let Some(path) = &options.atlas_path {
+        if let Err(e) = fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| textures.load_atlas(&bytes).map_err(|e| e.to_string()))
+        {
+            eprintln!("Error loading texture atlas {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+
+    let mut level = match &options.level_path {
+        Some(path) => {
+            let source = fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("Error reading {}: {}", path, e);
+                process::exit(1);
+            });
+            let level_error = |e| -> ! {
+                eprintln!("Error loading {}:{}", path, e);
+                process::exit(1);
+            };
+
+            // Texture paths are relative to the level file
+            let level_dir = Path::new(path).parent().unwrap_or(Path::new("."));
+            for (name, texture_path) in Level::texture_paths(&source).unwrap_or_else(|e| level_error(e)) {
+                let texture_path = level_dir.join(texture_path);
+                if let Err(e) = fs::read(&texture_path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|bytes| textures.load(&name, &bytes).map_err(|e| e.to_string()))
+                {
+                    eprintln!("Error loading texture {}: {}", texture_path.display(), e);
+                    process::exit(1);
+                }
+            }
+            Level::from_toml_with_textures(&source, &textures).unwrap_or_else(|e| level_error(e))
+        }
+        None => Level::demo(),
+    };
+    level.update(options.time);
+
+    let mut camera = Camera::new(Point::new(
+        options.x.unwrap_or(level.spawn.pos.x),
+        options.y.unwrap_or(level.spawn.pos.y),
+    ));
+    camera.rotation = options
+        .rotation
+        .map(Rotation::new)
+        .unwrap_or(level.spawn.rotation.clone());
+    camera.fov = options.fov;
+    camera.columns_per_ray = options.columns_per_ray;
+    camera.fish_eye_correction = options.fish_eye_correction;
+    camera.eye_height = options.eye_height;
+    camera.pitch = options.pitch;
+
+    let mut frame_buffer = FrameBuffer::new(options.width, options.height);
+    frame_buffer.draw_floor(&camera, &level);
+    frame_buffer.draw_walls(&camera, &level);
+    frame_buffer.draw_sprites(&camera, &level);
+    if options.minimap {
+        frame_buffer.draw_minimap(&camera, &level);
+    }
+
+    if let Err(e) = image::save_buffer(
+        &options.output,
+        frame_buffer.buffer(),
+        options.width as u32,
+        options.height as u32,
+        image::ColorType::Rgba8,
+    ) {
+        eprintln!("Error writing {}: {}", options.output, e);
+        process::exit(1);
+    }
+}
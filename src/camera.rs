@@ -0,0 +1,186 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::level::Level;
+use crate::math::{Point, Rotation};
+
+// Units per second
+pub const MOVEMENT_SPEED_MODIFIER: f32 = 2.0;
+pub const STANDING_EYE_HEIGHT: f32 = 0.5;
+pub const CROUCHING_EYE_HEIGHT: f32 = 0.3;
+// How far up or down the camera can look, in degrees
+const MAX_PITCH: f32 = 30.0;
+// Degrees per second
+const TURN_SPEED: f32 = 180.0;
+const LOOK_SPEED: f32 = 90.0;
+// Units per second
+const JUMP_SPEED: f32 = 2.0;
+const CROUCH_SPEED: f32 = 1.5;
+// Units per second squared
+const GRAVITY: f32 = 8.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct InputInfo {
+    pub forward: bool,
+    pub backward: bool,
+    pub right: bool,
+    pub left: bool,
+    pub rot_right: bool,
+    pub rot_left: bool,
+    /// Analog movement from -1.0 to 1.0 on top of the buttons, forward, right, turning right and
+    /// looking up being positive.
+    pub move_amount: f32,
+    pub strafe_amount: f32,
+    pub turn_amount: f32,
+    pub look_amount: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub pos: Point,
+    pub rotation: Rotation,
+    pub fov: u32,
+    pub columns_per_ray: u32,
+    pub fish_eye_correction: bool,
+    /// Height of the camera above the floor, 0.5 is halfway up a standard wall.
+    pub eye_height: f32,
+    /// Degrees the camera looks up (positive) or down, drawn by moving the horizon.
+    pub pitch: f32,
+    /// Units per second the camera is moving up while jumping, 0.0 when on the ground.
+    pub vertical_speed: f32,
+    pub crouching: bool,
+}
+
+impl Camera {
+    pub fn new(pos: Point) -> Camera {
+        Camera {
+            pos,
+            rotation: Rotation::new(0.0),
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: false,
+            eye_height: STANDING_EYE_HEIGHT,
+            pitch: 0.0,
+            vertical_speed: 0.0,
+            crouching: false,
+        }
+    }
+    pub fn mod_fov(&mut self, value: i32) {
+        self.fov = (((self.fov as i32) + value) as u32).clamp(1, 179);
+    }
+    pub fn mod_columns_per_ray(&mut self, value: i32) {
+        self.columns_per_ray = (((self.columns_per_ray as i32) + value) as u32).clamp(1, 16);
+    }
+    pub fn mod_pitch(&mut self, value: f32) {
+        self.pitch = (self.pitch + value).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+    /// The screen row the horizon is drawn at, pitch shears the view up or down instead of tilting
+    /// it.
+    pub fn horizon(&self, screen_height: usize) -> f32 {
+        (screen_height as f32) / 2.0 + (screen_height as f32) * self.pitch.to_radians().tan()
+    }
+    pub fn jump(&mut self) {
+        if self.vertical_speed == 0.0 {
+            self.vertical_speed = JUMP_SPEED;
+        }
+    }
+    /// Pulls a jumping camera back down and moves the eye height towards standing or crouching
+    /// height.
+    pub fn update_eye_height(&mut self, elapsed_seconds: f32) {
+        let ground_eye_height: f32 = if self.crouching {
+            CROUCHING_EYE_HEIGHT
+        } else {
+            STANDING_EYE_HEIGHT
+        };
+
+        if self.vertical_speed != 0.0 {
+            self.vertical_speed -= GRAVITY * elapsed_seconds;
+            self.eye_height += self.vertical_speed * elapsed_seconds;
+            if self.vertical_speed < 0.0 && self.eye_height <= ground_eye_height {
+                self.eye_height = ground_eye_height;
+                self.vertical_speed = 0.0;
+            }
+        } else if self.eye_height < ground_eye_height {
+            self.eye_height = (self.eye_height + CROUCH_SPEED * elapsed_seconds).min(ground_eye_height);
+        } else {
+            self.eye_height = (self.eye_height - CROUCH_SPEED * elapsed_seconds).max(ground_eye_height);
+        }
+    }
+    pub fn ray_count(&self, screen_width: usize) -> usize {
+        screen_width.div_ceil(self.columns_per_ray as usize)
+    }
+    /// One angle per ray, each pointing through the centre of its `columns_per_ray` wide strip of
+    /// the screen as projected onto the camera plane.
+    pub fn get_angles_to_cast(&self, screen_width: usize) -> Vec<Rotation> {
+        let plane_half_width: f32 = (self.fov as f32 / 2.0).to_radians().tan();
+        let columns_per_ray = self.columns_per_ray as usize;
+
+        (0..self.ray_count(screen_width))
+            .map(|i| {
+                let first_column: usize = i * columns_per_ray;
+                let center_column: f32 =
+                    first_column as f32 + (columns_per_ray.min(screen_width - first_column) as f32 / 2.0);
+                let camera_x: f32 = (2.0 * center_column / (screen_width as f32)) - 1.0;
+                Rotation::new(self.rotation.degree + (camera_x * plane_half_width).atan().to_degrees())
+            })
+            .collect()
+    }
+    /// Turns and moves the camera by what `input` holds down for `elapsed_seconds`, sliding along
+    /// walls it runs into.
+    pub fn update_from_input(&mut self, level: &Level, input: &InputInfo, elapsed_seconds: f32) {
+        let distance: f32 = MOVEMENT_SPEED_MODIFIER * elapsed_seconds;
+        let axis = |positive: bool, negative: bool, amount: f32| {
+            ((positive as i32 - negative as i32) as f32 + amount).clamp(-1.0, 1.0)
+        };
+        let forward: f32 = axis(input.forward, input.backward, input.move_amount);
+        let right: f32 = axis(input.right, input.left, input.strafe_amount);
+
+        self.rotation
+            .mod_value(axis(input.rot_right, input.rot_left, input.turn_amount) * TURN_SPEED * elapsed_seconds);
+        self.mod_pitch(input.look_amount.clamp(-1.0, 1.0) * LOOK_SPEED * elapsed_seconds);
+
+        let (forward_x, forward_y) = (
+            self.rotation.degree.to_radians().cos(),
+            self.rotation.degree.to_radians().sin(),
+        );
+        let (right_x, right_y) = (
+            (self.rotation.degree.to_radians() + FRAC_PI_2).cos(),
+            (self.rotation.degree.to_radians() + FRAC_PI_2).sin(),
+        );
+        let mut x_change: f32 = distance * (forward * forward_x + right * right_x);
+        let mut y_change: f32 = distance * (forward * forward_y + right * right_y);
+
+        // Moving diagonally isn't any faster than moving straight
+        let length: f32 = (x_change * x_change + y_change * y_change).sqrt();
+        if length > distance {
+            x_change *= distance / length;
+            y_change *= distance / length;
+        }
+
+        if !level.is_solid(&Point {
+            x: self.pos.x + x_change,
+            y: self.pos.y,
+        }) {
+            self.pos.x += x_change
+        }
+        if !level.is_solid(&Point {
+            x: self.pos.x,
+            y: self.pos.y + y_change,
+        }) {
+            self.pos.y += y_change
+        }
+    }
+    /// The camera `alpha` of the way from `previous` to this one, for drawing between two
+    /// simulation ticks.
+    pub fn interpolate(&self, previous: &Camera, alpha: f32) -> Camera {
+        let lerp = |from: f32, to: f32| from + (to - from) * alpha;
+        // Turn the short way round when the rotation wraps past 0 / 360 degrees
+        let rotation_change: f32 = (self.rotation.degree - previous.rotation.degree + 180.0).rem_euclid(360.0) - 180.0;
+        Camera {
+            pos: Point::new(lerp(previous.pos.x, self.pos.x), lerp(previous.pos.y, self.pos.y)),
+            rotation: Rotation::new(previous.rotation.degree + rotation_change * alpha),
+            eye_height: lerp(previous.eye_height, self.eye_height),
+            pitch: lerp(previous.pitch, self.pitch),
+            ..self.clone()
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::light::{Atmosphere, LightLevel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub fn new(red: u8, green: u8, blue: u8) -> Color {
+        Color::new_with_alpha(red, green, blue, 255)
+    }
+    pub fn new_with_alpha(red: u8, green: u8, blue: u8, alpha: u8) -> Color {
+        Color {
+            r: red,
+            g: green,
+            b: blue,
+            a: alpha,
+        }
+    }
+    /// Shades the color by distance in the default atmosphere, see `Atmosphere`.
+    pub fn shade_distance(&self, distance: f32) -> Color {
+        Atmosphere::default().shade(self, distance, LightLevel::default())
+    }
+    /// Shades the color by distance in a level's atmosphere, adding the light falling on it.
+    pub fn shade(&self, atmosphere: &Atmosphere, distance: f32, light: LightLevel) -> Color {
+        atmosphere.shade(self, distance, light)
+    }
+    /// Multiplies the color channels by `factor`, keeping alpha.
+    pub fn scale(&self, factor: f32) -> Color {
+        Color::new_with_alpha(
+            (self.r as f32 * factor).clamp(0.0, 255.0) as u8,
+            (self.g as f32 * factor).clamp(0.0, 255.0) as u8,
+            (self.b as f32 * factor).clamp(0.0, 255.0) as u8,
+            self.a,
+        )
+    }
+    /// Composites this color over `below` using this color's alpha.
+    pub fn blend_over(&self, below: Color) -> Color {
+        let alpha: f32 = self.a as f32 / 255.0;
+        Color::new(
+            (self.r as f32 * alpha + below.r as f32 * (1.0 - alpha)) as u8,
+            (self.g as f32 * alpha + below.g as f32 * (1.0 - alpha)) as u8,
+            (self.b as f32 * alpha + below.b as f32 * (1.0 - alpha)) as u8,
+        )
+    }
+}
@@ -0,0 +1,462 @@
+use std::ops::Range;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::level::{Entity, Level};
+use crate::math::Point;
+use crate::ray::{cast_ray, cast_ray_layers, Face};
+use crate::texture::Texture;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub color: Color,
+}
+
+impl Rect {
+    fn fit_to_screen(&self, dest_width: usize, dest_height: usize) -> Rect {
+        let mut rect_temp: Rect = *self;
+
+        if rect_temp.x >= dest_width {
+            rect_temp.x = dest_width - 1
+        }
+        if rect_temp.y >= dest_height {
+            rect_temp.y = dest_height - 1
+        }
+        if rect_temp.x + rect_temp.width >= dest_width {
+            rect_temp.width = dest_width - rect_temp.x
+        }
+        if rect_temp.y + rect_temp.height >= dest_height {
+            rect_temp.height = dest_height - rect_temp.y
+        }
+
+        rect_temp
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+/// An RGBA8 pixel buffer the renderer draws into.
+pub struct FrameBuffer {
+    buffer: Vec<u8>,
+    width: usize,
+    height: usize,
+    // Distance to the nearest wall drawn at every pixel, for clipping sprites
+    depth: Vec<f32>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: usize, height: usize) -> FrameBuffer {
+        FrameBuffer {
+            width,
+            height,
+            buffer: vec![0; width * height * 4],
+            depth: vec![f32::INFINITY; width * height],
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+    // Writes an opaque pixel, blending translucent colors over what is already there
+    fn put_color(&mut self, index: usize, color: Color) {
+        let color = match color.a {
+            0 => return,
+            255 => color,
+            _ => color.blend_over(Color::new(
+                self.buffer[index],
+                self.buffer[index + 1],
+                self.buffer[index + 2],
+            )),
+        };
+
+        self.buffer[index] = color.r;
+        self.buffer[index + 1] = color.g;
+        self.buffer[index + 2] = color.b;
+        self.buffer[index + 3] = 255;
+    }
+    pub fn draw_pixel(&mut self, pos: Point, color: Color) {
+        if pos.x >= 0.0 && pos.x < (self.width as f32) && pos.y >= 0.0 && pos.y < (self.height as f32) {
+            let index: usize = (((pos.y as usize) * self.width) + (pos.x as usize)) * 4;
+            self.put_color(index, color);
+        }
+    }
+    pub fn draw_rect(&mut self, rect: Rect) {
+        self.fill_rect(rect, None);
+    }
+    // Like `draw_rect`, also recording `depth` for every pixel that isn't fully transparent
+    fn fill_rect(&mut self, rect: Rect, depth: Option<f32>) {
+        let rect_temp = rect.fit_to_screen(self.width, self.height);
+
+        for y in 0..rect_temp.height {
+            for x in 0..rect_temp.width {
+                let pos: usize = ((rect_temp.y + y) * self.width) + rect_temp.x + x;
+                self.put_color(pos * 4, rect_temp.color);
+                if let Some(depth) = depth {
+                    if rect_temp.color.a > 0 {
+                        self.depth[pos] = depth;
+                    }
+                }
+            }
+        }
+    }
+    // The rows whose centre lies between two screen heights
+    fn rows_between(&self, from: f32, to: f32) -> Range<usize> {
+        let first: usize = (from - 0.5).ceil().clamp(0.0, self.height as f32) as usize;
+        let last: usize = (to - 0.5).ceil().clamp(0.0, self.height as f32) as usize;
+        first..last.max(first)
+    }
+    pub fn draw_line(&mut self, p0: Point, p1: Point, color: Color) {
+        let (dx, dy) = ((p0.x - p1.x).abs(), -(p0.y - p1.y).abs());
+        let (sx, sy) = (
+            (if p0.x < p1.x { 1.0 } else { -1.0 }),
+            (if p0.y < p1.y { 1.0 } else { -1.0 }),
+        );
+        let mut error = dx + dy;
+
+        let mut point: Point = p0;
+        for _ in 0..(((self.width.pow(2) * self.height.pow(2)) as f32).powf(0.5) as usize) {
+            self.draw_pixel(point, color);
+
+            if point.x == p1.x && point.y == p1.y {
+                break;
+            }
+            if (error * 2.0) >= dy {
+                if point.x == p1.x {
+                    break;
+                }
+                error += dy;
+                point.x += sx;
+            }
+            if (error * 2.0) <= dx {
+                if point.y == p1.y {
+                    break;
+                }
+                error += dx;
+                point.y += sy;
+            }
+        }
+    }
+    /// Casts the floor and ceiling of every walkable tile, the ceiling being one unit above the
+    /// floor. Tiles without a ceiling show the level's sky.
+    pub fn draw_floor(&mut self, camera: &Camera, level: &Level) {
+        let angles = camera.get_angles_to_cast(self.width);
+        let horizon: i64 = camera.horizon(self.height).round() as i64;
+        let sky: Option<&Texture> = level.get_sky_texture();
+
+        for x in 0..self.width {
+            let angle = &angles[x / camera.columns_per_ray as usize];
+            let ray_dir: (f32, f32) = (angle.degree.to_radians().cos(), angle.degree.to_radians().sin());
+            let fish_eye_factor: f32 = if camera.fish_eye_correction {
+                (angle.degree - camera.rotation.degree).to_radians().cos()
+            } else {
+                1.0
+            };
+
+            for y in 0..self.height {
+                let rows_from_horizon: usize = (y as i64 - horizon).unsigned_abs() as usize;
+                // The distance at which the floor / ceiling lands on this row
+                let row_distance: f32 = (self.height as f32)
+                    * if y as i64 > horizon {
+                        camera.eye_height
+                    } else {
+                        1.0 - camera.eye_height
+                    }
+                    / (rows_from_horizon as f32);
+                let floor_point = Point::new(
+                    camera.pos.x + ray_dir.0 * row_distance / fish_eye_factor,
+                    camera.pos.y + ray_dir.1 * row_distance / fish_eye_factor,
+                );
+
+                let texture: Option<&Texture> = if y as i64 > horizon {
+                    level.get_floor_texture(&floor_point)
+                } else if (y as i64) < horizon {
+                    level.get_ceiling_texture(&floor_point)
+                } else {
+                    None
+                };
+                let color: Color = match (texture, sky) {
+                    (Some(texture), _) => texture
+                        .get_color(&Point::new(
+                            (floor_point.x - floor_point.x.floor()) * (texture.width as f32),
+                            (floor_point.y - floor_point.y.floor()) * (texture.height as f32),
+                        ))
+                        .shade(&level.atmosphere, row_distance, level.get_light(&floor_point)),
+                    // The sky scrolls with the ray's angle and stands a screen height tall on the horizon
+                    (None, Some(sky)) if (y as i64) < horizon => *sky.get_color(&Point::new(
+                        angle.degree.rem_euclid(360.0) / 360.0 * (sky.width as f32),
+                        ((1.0 - rows_from_horizon as f32 / self.height as f32) * (sky.height as f32)).max(0.0),
+                    )),
+                    _ => {
+                        let value: u8 = ((255.0 / ((self.height as f32) * 0.8)) * (rows_from_horizon as f32) / 2.0)
+                            .clamp(0.0, 255.0) as u8;
+                        Color::new(value, value, value)
+                    }
+                };
+                self.draw_pixel(Point::new(x as f32, y as f32), color);
+            }
+        }
+    }
+    pub fn draw_minimap(&mut self, camera: &Camera, level: &Level) {
+        const TILE_SIZE: usize = 16;
+
+        if self.width < (level.width() + 2) * TILE_SIZE || self.height < (level.height() + 2) * TILE_SIZE {
+            return;
+        }
+
+        for y in 0..level.height() {
+            for x in 0..level.width() {
+                let tile_pos = &Point::new(x as f32, y as f32);
+                let tile = level.get_tile(tile_pos);
+                let texture = level.get_texture(tile_pos);
+                self.draw_rect(Rect {
+                    x: self.width - ((x + 2) * TILE_SIZE),
+                    y: (y + 1) * TILE_SIZE,
+                    width: TILE_SIZE,
+                    height: TILE_SIZE,
+                    color: if !tile.transparent {
+                        Color {
+                            a: 255,
+                            ..texture.layout[0]
+                        }
+                    } else {
+                        Color::new(255, 255, 255)
+                    },
+                });
+
+                // Faces with their own texture get a strip along their edge, the minimap is mirrored
+                // horizontally so east is on the left
+                if !tile.transparent {
+                    for face in [Face::North, Face::South, Face::East, Face::West] {
+                        if tile.face_texture_index(face) == tile.texture_index {
+                            continue;
+                        }
+                        let (strip_x, strip_y, strip_width, strip_height) = match face {
+                            Face::North => (0, 0, TILE_SIZE, TILE_SIZE / 4),
+                            Face::South => (0, TILE_SIZE - TILE_SIZE / 4, TILE_SIZE, TILE_SIZE / 4),
+                            Face::East => (0, 0, TILE_SIZE / 4, TILE_SIZE),
+                            Face::West => (TILE_SIZE - TILE_SIZE / 4, 0, TILE_SIZE / 4, TILE_SIZE),
+                        };
+                        self.draw_rect(Rect {
+                            x: self.width - ((x + 2) * TILE_SIZE) + strip_x,
+                            y: (y + 1) * TILE_SIZE + strip_y,
+                            width: strip_width,
+                            height: strip_height,
+                            color: Color {
+                                a: 255,
+                                ..level.get_face_texture(tile_pos, face).layout[0]
+                            },
+                        });
+                    }
+                }
+
+                // Doors are a bar along their panel that shrinks as they open, mirrored like the rest of the minimap
+                if let Some(door) = level.get_door((x as i32, y as i32)) {
+                    let panel_length: usize = (((1.0 - door.open_amount) * TILE_SIZE as f32) as usize).max(1);
+                    let (bar_x, bar_y, bar_width, bar_height) = if door.horizontal {
+                        (0, TILE_SIZE / 2 - 1, panel_length, 2)
+                    } else {
+                        (TILE_SIZE / 2 - 1, TILE_SIZE - panel_length, 2, panel_length)
+                    };
+                    self.draw_rect(Rect {
+                        x: self.width - ((x + 2) * TILE_SIZE) + bar_x,
+                        y: (y + 1) * TILE_SIZE + bar_y,
+                        width: bar_width,
+                        height: bar_height,
+                        color: Color {
+                            a: 255,
+                            ..texture.layout[0]
+                        },
+                    });
+                }
+            }
+        }
+
+        // Rays leaving a level without an outer wall and cameras outside the level are clamped to
+        // the edge of the minimap
+        let screen_width: usize = self.width;
+        let to_minimap = |point: &Point| {
+            let x: f32 = point.x.clamp(0.0, level.width() as f32);
+            let y: f32 = point.y.clamp(0.0, level.height() as f32);
+            Point::new(
+                (screen_width - ((x + 1.0) * (TILE_SIZE as f32)) as usize) as f32,
+                (((y + 1.0) * (TILE_SIZE as f32)) as usize) as f32,
+            )
+        };
+        for angle in camera.get_angles_to_cast(self.width) {
+            let cast_result: Point = cast_ray(&camera.pos, &angle, level).point;
+            self.draw_line(to_minimap(&camera.pos), to_minimap(&cast_result), Color::new(0, 255, 0));
+        }
+
+        if level.is_in_level(&camera.pos) {
+            self.draw_rect(Rect {
+                x: self.width - ((camera.pos.x + 1.0) * (TILE_SIZE as f32)) as usize - TILE_SIZE / 4,
+                y: ((camera.pos.y + 1.0) * (TILE_SIZE as f32)) as usize - TILE_SIZE / 4,
+                width: TILE_SIZE / 2,
+                height: TILE_SIZE / 2,
+                color: Color::new(255, 0, 0),
+            })
+        }
+    }
+    /// Draws every wall the rays hit far to near, with the tops and bottoms of walls the camera
+    /// looks down or up at.
+    pub fn draw_walls(&mut self, camera: &Camera, level: &Level) {
+        const MAX_RAY_LAYERS: usize = 16;
+        let slice_width: usize = camera.columns_per_ray as usize;
+        let (screen_height, horizon): (f32, f32) = (self.height as f32, camera.horizon(self.height));
+        // The screen row a height in the world lands on at a distance
+        let screen_y = |z: f32, distance: f32| horizon + (camera.eye_height - z) * screen_height / distance;
+
+        for (slice_index, angle) in camera.get_angles_to_cast(self.width).into_iter().enumerate() {
+            let ray_dir: (f32, f32) = (angle.degree.to_radians().cos(), angle.degree.to_radians().sin());
+            let fish_eye_factor: f32 = if camera.fish_eye_correction {
+                (angle.degree - camera.rotation.degree).to_radians().cos()
+            } else {
+                1.0
+            };
+            let slice_x: usize = slice_width * slice_index;
+
+            // Far to near, so closer and see-through walls are composited over what's behind them
+            for hit in cast_ray_layers(&camera.pos, &angle, level, MAX_RAY_LAYERS)
+                .into_iter()
+                .rev()
+            {
+                let tile = level.get_tile(&hit.tile_point());
+                if !tile.door && tile.transparent {
+                    continue;
+                }
+                let wall_distance: f32 = hit.distance * fish_eye_factor;
+                let exit_distance: f32 = hit.exit_distance * fish_eye_factor;
+                let (bottom, top) = (tile.elevation, tile.top());
+
+                let cap_texture: &Texture = level.get_texture(&hit.tile_point());
+                for (cap_z, visible) in [(top, top < camera.eye_height), (bottom, bottom > camera.eye_height)] {
+                    if !visible {
+                        continue;
+                    }
+                    let (near_y, far_y) = (screen_y(cap_z, wall_distance), screen_y(cap_z, exit_distance));
+                    for y in self.rows_between(near_y.min(far_y), near_y.max(far_y)) {
+                        let distance: f32 = ((camera.eye_height - cap_z) * screen_height / (y as f32 + 0.5 - horizon))
+                            .clamp(wall_distance, exit_distance);
+                        let cap_point = Point::new(
+                            camera.pos.x + ray_dir.0 * distance / fish_eye_factor,
+                            camera.pos.y + ray_dir.1 * distance / fish_eye_factor,
+                        );
+                        let color = cap_texture
+                            .get_color(&Point::new(
+                                (cap_point.x - cap_point.x.floor()) * (cap_texture.width as f32),
+                                (cap_point.y - cap_point.y.floor()) * (cap_texture.height as f32),
+                            ))
+                            .shade(&level.atmosphere, distance, level.get_light(&cap_point));
+                        self.fill_rect(
+                            Rect {
+                                x: slice_x,
+                                y,
+                                width: slice_width,
+                                height: 1,
+                                color,
+                            },
+                            Some(distance),
+                        );
+                    }
+                }
+
+                // The texture repeats every unit of height, starting at the top of the wall
+                let texture: &Texture = level.get_face_texture(&hit.tile_point(), hit.face);
+                let texture_x: f32 = (hit.wall_offset * (texture.width as f32)).min(texture.width as f32 - 1.0);
+                let light = level.get_face_light(hit.tile, hit.face, &hit.point);
+                let face_brightness: f32 = level.face_shading.brightness(hit.face);
+                for y in self.rows_between(screen_y(top, wall_distance), screen_y(bottom, wall_distance)) {
+                    let z: f32 = camera.eye_height - (y as f32 + 0.5 - horizon) * wall_distance / screen_height;
+                    let texture_y: f32 =
+                        ((top - z).rem_euclid(1.0) * (texture.height as f32)).min(texture.height as f32 - 1.0);
+                    self.fill_rect(
+                        Rect {
+                            x: slice_x,
+                            y,
+                            width: slice_width,
+                            height: 1,
+                            color: texture
+                                .get_color(&Point::new(texture_x, texture_y))
+                                .scale(face_brightness)
+                                .shade(&level.atmosphere, wall_distance, light),
+                        },
+                        Some(wall_distance),
+                    );
+                }
+            }
+        }
+    }
+    /// Draws the level's entities as billboards facing the camera, far to near and hidden behind
+    /// walls closer than them. Has to be called after `draw_walls`.
+    pub fn draw_sprites(&mut self, camera: &Camera, level: &Level) {
+        let plane_half_width: f32 = (camera.fov as f32 / 2.0).to_radians().tan();
+        let (dir_x, dir_y) = (
+            camera.rotation.degree.to_radians().cos(),
+            camera.rotation.degree.to_radians().sin(),
+        );
+
+        // Position relative to the camera as (distance in front of it, distance to its right)
+        let mut sprites: Vec<(&Entity, f32, f32)> = level
+            .entities
+            .iter()
+            .map(|entity| {
+                let (x, y) = (entity.pos.x - camera.pos.x, entity.pos.y - camera.pos.y);
+                (entity, x * dir_x + y * dir_y, y * dir_x - x * dir_y)
+            })
+            .filter(|(_, forward, _)| *forward > 0.01)
+            .collect();
+        sprites.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        for (entity, forward, right) in sprites {
+            // Matches how far away walls are measured, so sprites line up with the floor and walls
+            let distance: f32 = if camera.fish_eye_correction {
+                forward
+            } else {
+                (forward * forward + right * right).sqrt()
+            };
+            let texture: &Texture = level.get_entity_texture(entity);
+            let frame_width: usize = texture.width / entity.frame_count();
+            if frame_width == 0 {
+                continue;
+            }
+            let light = level.get_light(&entity.pos);
+            let frame_x: f32 = (entity.frame(&camera.rotation) * frame_width) as f32;
+            let floor_y: f32 = camera.horizon(self.height) + camera.eye_height * (self.height as f32) / distance;
+            let sprite_height: f32 = (self.height as f32) * entity.scale / distance;
+            let sprite_width: f32 = sprite_height * (frame_width as f32) / (texture.height as f32);
+            let center_x: f32 = (right / (forward * plane_half_width) + 1.0) * (self.width as f32) / 2.0;
+            let (left, top) = (center_x - sprite_width / 2.0, floor_y - sprite_height);
+
+            let first_column = left.max(0.0) as usize;
+            let last_column = ((left + sprite_width).ceil().max(0.0) as usize).min(self.width);
+            let first_row = top.max(0.0) as usize;
+            let last_row = (floor_y.ceil().max(0.0) as usize).min(self.height);
+            for x in first_column..last_column {
+                let texture_x: f32 = frame_x
+                    + ((x as f32 + 0.5 - left) / sprite_width * (frame_width as f32))
+                        .clamp(0.0, frame_width as f32 - 1.0);
+                for y in first_row..last_row {
+                    if self.depth[y * self.width + x] < distance {
+                        continue;
+                    }
+                    let texture_y: f32 = ((y as f32 + 0.5 - top) / sprite_height * (texture.height as f32))
+                        .clamp(0.0, texture.height as f32 - 1.0);
+                    let color =
+                        texture
+                            .get_color(&Point::new(texture_x, texture_y))
+                            .shade(&level.atmosphere, distance, light);
+                    self.put_color((y * self.width + x) * 4, color);
+                }
+            }
+        }
+    }
+}
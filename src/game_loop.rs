@@ -0,0 +1,34 @@
+// Frames slower than this are simulated as if they took this long, so a stalled tab doesn't
+// have to catch up on seconds of ticks at once
+const MAX_FRAME_SECONDS: f32 = 0.25;
+
+/// Splits the time between rendered frames into simulation ticks of a fixed length, so the game
+/// behaves the same at any frame rate.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    /// Seconds simulated by every tick.
+    pub tick_seconds: f32,
+    // Time passed that hasn't been simulated yet, always less than one tick after `advance`
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(ticks_per_second: u32) -> FixedTimestep {
+        FixedTimestep {
+            tick_seconds: 1.0 / ticks_per_second as f32,
+            accumulator: 0.0,
+        }
+    }
+    /// Adds the seconds since the last frame and returns how many ticks to simulate for them.
+    pub fn advance(&mut self, elapsed_seconds: f32) -> usize {
+        self.accumulator += elapsed_seconds.clamp(0.0, MAX_FRAME_SECONDS);
+        let ticks = (self.accumulator / self.tick_seconds).floor();
+        self.accumulator -= ticks * self.tick_seconds;
+        ticks as usize
+    }
+    /// How far the time left over after the last `advance` is into the next tick, from 0.0 to 1.0,
+    /// to interpolate between the last two simulated states with when rendering.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.tick_seconds).clamp(0.0, 1.0)
+    }
+}
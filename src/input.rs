@@ -0,0 +1,296 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::InputInfo;
+
+// Holding the right mouse button walks forward
+const MOUSE_BUTTON_FORWARD: i16 = 2;
+
+// Axes and buttons of a gamepad with the browser's "standard" mapping
+const AXIS_LEFT_X: usize = 0;
+const AXIS_LEFT_Y: usize = 1;
+const AXIS_RIGHT_X: usize = 2;
+const AXIS_RIGHT_Y: usize = 3;
+const GAMEPAD_BUTTONS: [(usize, Action); 6] = [
+    (0, Action::Jump),
+    (1, Action::ToggleCrouch),
+    (2, Action::UseDoor),
+    (3, Action::ToggleFishEyeCorrection),
+    (4, Action::DecreaseFov),
+    (5, Action::IncreaseFov),
+];
+
+/// Something the player can do by pressing a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    StrafeRight,
+    StrafeLeft,
+    TurnRight,
+    TurnLeft,
+    Jump,
+    ToggleCrouch,
+    UseDoor,
+    IncreaseResolution,
+    DecreaseResolution,
+    ToggleFishEyeCorrection,
+    DecreaseFov,
+    IncreaseFov,
+}
+
+#[derive(Debug)]
+pub enum KeyBindingsError {
+    Syntax(toml::de::Error),
+    InvalidGamepadSettings(&'static str),
+}
+
+impl fmt::Display for KeyBindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyBindingsError::Syntax(e) => write!(f, "invalid key bindings: {}", e),
+            KeyBindingsError::InvalidGamepadSettings(reason) => write!(f, "invalid gamepad settings: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for KeyBindingsError {}
+
+/// Which keys trigger which action, by `KeyboardEvent.code` so they stay in the same place on
+/// every keyboard layout.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    actions: HashMap<String, Action>,
+    pub gamepad: GamepadSettings,
+}
+
+/// How gamepad sticks are read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct GamepadSettings {
+    /// How far a stick has to be pushed, from 0.0 to 1.0, before it does anything.
+    pub dead_zone: f32,
+    /// Multiplies how fast the left stick moves and the right stick turns and looks up and down.
+    pub move_sensitivity: f32,
+    pub turn_sensitivity: f32,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> GamepadSettings {
+        GamepadSettings {
+            dead_zone: 0.15,
+            move_sensitivity: 1.0,
+            turn_sensitivity: 1.0,
+        }
+    }
+}
+
+impl GamepadSettings {
+    /// Zero inside the dead zone, rising from there to 1.0 at the edge so small pushes stay smooth.
+    pub fn apply_dead_zone(&self, value: f32) -> f32 {
+        if value == 0.0 || value.abs() <= self.dead_zone {
+            return 0.0;
+        }
+        value.signum() * ((value.abs() - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0)
+    }
+    // A dead zone of 1.0 or more would divide by zero in `apply_dead_zone`
+    fn validate(&self) -> Result<(), KeyBindingsError> {
+        if !(0.0..1.0).contains(&self.dead_zone) {
+            return Err(KeyBindingsError::InvalidGamepadSettings(
+                "dead_zone must be at least 0.0 and less than 1.0",
+            ));
+        }
+        if !(self.move_sensitivity >= 0.0 && self.turn_sensitivity >= 0.0) {
+            return Err(KeyBindingsError::InvalidGamepadSettings(
+                "sensitivities must be 0.0 or more",
+            ));
+        }
+        Ok(())
+    }
+}
+
+// The key bindings file, every action with its keys next to an optional [gamepad] table
+#[derive(Serialize, Deserialize)]
+struct KeyBindingsFile {
+    gamepad: Option<GamepadSettings>,
+    #[serde(flatten)]
+    actions: BTreeMap<Action, Vec<String>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        let mut bindings = KeyBindings {
+            actions: HashMap::new(),
+            gamepad: GamepadSettings::default(),
+        };
+        for (action, code) in [
+            (Action::MoveForward, "KeyW"),
+            (Action::MoveBackward, "KeyS"),
+            (Action::StrafeRight, "KeyD"),
+            (Action::StrafeLeft, "KeyA"),
+            (Action::TurnRight, "KeyE"),
+            (Action::TurnLeft, "KeyQ"),
+            (Action::Jump, "Space"),
+            (Action::ToggleCrouch, "KeyC"),
+            (Action::UseDoor, "KeyF"),
+            (Action::DecreaseResolution, "Numpad1"),
+            (Action::IncreaseResolution, "Numpad2"),
+            (Action::ToggleFishEyeCorrection, "Numpad3"),
+            (Action::DecreaseFov, "Numpad4"),
+            (Action::IncreaseFov, "Numpad5"),
+        ] {
+            bindings.bind(action, code);
+        }
+        bindings
+    }
+}
+
+impl KeyBindings {
+    /// The default bindings with the actions listed in `source` rebound, see `key_bindings.toml`
+    /// for the format.
+    pub fn from_toml(source: &str) -> Result<KeyBindings, KeyBindingsError> {
+        let mut bindings = KeyBindings::default();
+        bindings.apply_toml(source)?;
+        Ok(bindings)
+    }
+    /// Rebinds the actions listed in `source`, keeping the keys of the others.
+    pub fn apply_toml(&mut self, source: &str) -> Result<(), KeyBindingsError> {
+        let file: KeyBindingsFile = toml::from_str(source).map_err(KeyBindingsError::Syntax)?;
+        if let Some(gamepad) = file.gamepad {
+            gamepad.validate()?;
+            self.gamepad = gamepad;
+        }
+        for (action, codes) in file.actions {
+            self.actions.retain(|_, bound_action| *bound_action != action);
+            for code in codes {
+                self.bind(action, &code);
+            }
+        }
+        Ok(())
+    }
+    /// Every action with the keys bound to it and the gamepad settings, in the format `from_toml`
+    /// reads.
+    pub fn to_toml(&self) -> String {
+        let mut actions: BTreeMap<Action, Vec<String>> = BTreeMap::new();
+        for (code, action) in &self.actions {
+            actions.entry(*action).or_default().push(code.clone());
+        }
+        actions.values_mut().for_each(|codes| codes.sort());
+        toml::to_string(&KeyBindingsFile {
+            gamepad: Some(self.gamepad.clone()),
+            actions,
+        })
+        .expect("key bindings should serialize")
+    }
+    /// Makes `code` trigger `action`, taking it away from the action it triggered before.
+    pub fn bind(&mut self, action: Action, code: &str) {
+        self.actions.insert(code.to_string(), action);
+    }
+    pub fn action(&self, code: &str) -> Option<Action> {
+        self.actions.get(code).copied()
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+/// The keys and mouse buttons held down right now, kept up to date by press and release events
+/// and sampled once per simulation tick.
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    pub bindings: KeyBindings,
+    held_keys: HashSet<String>,
+    held_mouse_buttons: HashSet<i16>,
+    gamepad: GamepadState,
+}
+
+// The last polled state of the gamepad in use
+#[derive(Debug, Clone, Default)]
+struct GamepadState {
+    axes: Vec<f32>,
+    buttons: Vec<bool>,
+}
+
+impl InputState {
+    pub fn new(bindings: KeyBindings) -> InputState {
+        InputState {
+            bindings,
+            held_keys: HashSet::new(),
+            held_mouse_buttons: HashSet::new(),
+            gamepad: GamepadState::default(),
+        }
+    }
+    /// Holds the key down and returns the action bound to it.
+    pub fn key_down(&mut self, code: &str) -> Option<Action> {
+        self.held_keys.insert(code.to_string());
+        self.bindings.action(code)
+    }
+    pub fn key_up(&mut self, code: &str) {
+        self.held_keys.remove(code);
+    }
+    pub fn mouse_down(&mut self, button: i16) {
+        self.held_mouse_buttons.insert(button);
+    }
+    pub fn mouse_up(&mut self, button: i16) {
+        self.held_mouse_buttons.remove(&button);
+    }
+    /// Stores the stick positions and pressed buttons of a gamepad polled this frame, and returns
+    /// the actions of the buttons that weren't pressed the last time.
+    pub fn update_gamepad(&mut self, axes: &[f32], buttons: &[bool]) -> Vec<Action> {
+        let was_pressed = |button: usize| self.gamepad.buttons.get(button).copied().unwrap_or(false);
+        let pressed: Vec<Action> = GAMEPAD_BUTTONS
+            .iter()
+            .filter(|(button, _)| buttons.get(*button).copied().unwrap_or(false) && !was_pressed(*button))
+            .map(|(_, action)| *action)
+            .collect();
+        self.gamepad = GamepadState {
+            axes: axes.to_vec(),
+            buttons: buttons.to_vec(),
+        };
+        pressed
+    }
+    /// Forgets everything held, for when the page loses focus and the release events go elsewhere.
+    pub fn release_all(&mut self) {
+        self.held_keys.clear();
+        self.held_mouse_buttons.clear();
+        self.gamepad = GamepadState::default();
+    }
+    // A gamepad stick axis after the dead zone, pushing up or right is positive
+    fn gamepad_axis(&self, axis: usize) -> f32 {
+        self.bindings
+            .gamepad
+            .apply_dead_zone(self.gamepad.axes.get(axis).copied().unwrap_or(0.0))
+    }
+    pub fn is_mouse_button_held(&self, button: i16) -> bool {
+        self.held_mouse_buttons.contains(&button)
+    }
+    /// Whether any key bound to `action` is held.
+    pub fn is_held(&self, action: Action) -> bool {
+        (action == Action::MoveForward && self.is_mouse_button_held(MOUSE_BUTTON_FORWARD))
+            || self
+                .held_keys
+                .iter()
+                .any(|code| self.bindings.action(code) == Some(action))
+    }
+    /// The movement the held keys and buttons and the gamepad's sticks ask for, opposite keys
+    /// cancel out.
+    pub fn input_info(&self) -> InputInfo {
+        let (forward, backward) = (self.is_held(Action::MoveForward), self.is_held(Action::MoveBackward));
+        let (right, left) = (self.is_held(Action::StrafeRight), self.is_held(Action::StrafeLeft));
+        let (rot_right, rot_left) = (self.is_held(Action::TurnRight), self.is_held(Action::TurnLeft));
+        InputInfo {
+            forward: forward && !backward,
+            backward: backward && !forward,
+            right: right && !left,
+            left: left && !right,
+            rot_right: rot_right && !rot_left,
+            rot_left: rot_left && !rot_right,
+            move_amount: -self.gamepad_axis(AXIS_LEFT_Y) * self.bindings.gamepad.move_sensitivity,
+            strafe_amount: self.gamepad_axis(AXIS_LEFT_X) * self.bindings.gamepad.move_sensitivity,
+            turn_amount: self.gamepad_axis(AXIS_RIGHT_X) * self.bindings.gamepad.turn_sensitivity,
+            look_amount: -self.gamepad_axis(AXIS_RIGHT_Y) * self.bindings.gamepad.turn_sensitivity,
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+
+use crate::light::{Atmosphere, FaceShading, Light, LightLevel, Lightmap};
+use crate::math::{clamp_degrees, Point, Rotation};
+use crate::ray::Face;
+use crate::texture::{Animation, Texture};
+
+// Open amount per second
+const DOOR_SPEED: f32 = 1.0;
+// How open a door has to be to walk through it
+const DOOR_PASSABLE_AMOUNT: f32 = 0.9;
+// How far away a door can be used from
+const DOOR_USE_DISTANCE: f32 = 1.5;
+
+#[derive(Debug, Clone)]
+pub struct Level {
+    layout: Vec<Vec<u8>>,
+    all_tiles: Vec<Tile>,
+    all_textures: Vec<Texture>,
+    width: usize,
+    height: usize,
+    doors: HashMap<(i32, i32), Door>,
+    animations: HashMap<u8, Animation>,
+    max_wall_top: f32,
+    lightmap: Lightmap,
+    // Seconds passed in `update`, drives animations
+    time: f32,
+    pub spawn: Spawn,
+    pub entities: Vec<Entity>,
+    /// Lights baked into the lightmap by `bake_lightmap`, so walls cast shadows from them.
+    pub lights: Vec<Light>,
+    /// Lights added on top of the lightmap every frame, for lights that move or change.
+    pub dynamic_lights: Vec<Light>,
+    pub atmosphere: Atmosphere,
+    pub face_shading: FaceShading,
+    /// A panorama covering all 360 degrees, drawn above tiles without a ceiling.
+    pub sky_texture_index: Option<u8>,
+}
+
+impl Level {
+    pub fn new(layout: Vec<Vec<u8>>, all_tiles: Vec<Tile>, all_textures: Vec<Texture>) -> Level {
+        let (layout_width, layout_height) = (layout[0].len(), layout.len());
+        let max_wall_top: f32 = all_tiles
+            .iter()
+            .filter(|tile| !tile.transparent || tile.door)
+            .map(|tile| tile.top())
+            .fold(1.0, f32::max);
+        let mut level = Level {
+            width: layout_width,
+            height: layout_height,
+            layout,
+            all_tiles,
+            all_textures,
+            doors: HashMap::new(),
+            animations: HashMap::new(),
+            time: 0.0,
+            max_wall_top,
+            lightmap: Lightmap::default(),
+            spawn: Spawn {
+                pos: Point::new(layout_width as f32 / 2.0, layout_height as f32 / 2.0),
+                rotation: Rotation::new(0.0),
+            },
+            entities: vec![],
+            lights: vec![],
+            dynamic_lights: vec![],
+            atmosphere: Atmosphere::default(),
+            face_shading: FaceShading::default(),
+            sky_texture_index: None,
+        };
+
+        for y in 0..layout_height as i32 {
+            for x in 0..layout_width as i32 {
+                if level.get_tile(&Point::new(x as f32, y as f32)).door {
+                    // A door between walls on its west and east is walked through north to south
+                    let is_wall = |x: i32| !level.get_tile(&Point::new(x as f32, y as f32)).transparent;
+                    let horizontal = is_wall(x - 1) && is_wall(x + 1);
+                    level.doors.insert(
+                        (x, y),
+                        Door {
+                            horizontal,
+                            open_amount: 0.0,
+                            opening: false,
+                        },
+                    );
+                }
+            }
+        }
+        level
+    }
+    /// The 13x15 level the web demo starts in.
+    pub fn demo() -> Level {
+        Level::from_toml(include_str!("../levels/demo.toml")).expect("levels/demo.toml should be a valid level")
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// The height of the top of the tallest wall, walls reaching it from the floor hide everything
+    /// behind them.
+    pub fn max_wall_top(&self) -> f32 {
+        self.max_wall_top
+    }
+    pub fn get_tile(&self, point: &Point) -> &Tile {
+        if (point.x >= 0.0 && point.x < (self.width as f32)) && (point.y >= 0.0 && point.y < (self.height as f32)) {
+            &self.all_tiles[self.layout[point.y as usize][point.x as usize] as usize]
+        } else {
+            &self.all_tiles[0]
+        }
+    }
+    /// Makes the texture at `texture_index` cycle through the animation's frames as time passes.
+    pub fn set_animation(&mut self, texture_index: u8, animation: Animation) {
+        self.animations.insert(texture_index, animation);
+    }
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+    // Resolves animated textures to their current frame
+    fn texture_at_index(&self, index: u8) -> &Texture {
+        match self.animations.get(&index) {
+            Some(animation) => &self.all_textures[animation.frame_at(self.time) as usize],
+            None => &self.all_textures[index as usize],
+        }
+    }
+    pub fn get_texture(&self, point: &Point) -> &Texture {
+        self.texture_at_index(self.get_tile(point).texture_index)
+    }
+    pub fn get_face_texture(&self, point: &Point, face: Face) -> &Texture {
+        self.texture_at_index(self.get_tile(point).face_texture_index(face))
+    }
+    pub fn get_floor_texture(&self, point: &Point) -> Option<&Texture> {
+        self.get_tile(point)
+            .floor_texture_index
+            .map(|index| self.texture_at_index(index))
+    }
+    pub fn get_ceiling_texture(&self, point: &Point) -> Option<&Texture> {
+        self.get_tile(point)
+            .ceiling_texture_index
+            .map(|index| self.texture_at_index(index))
+    }
+    pub fn get_sky_texture(&self) -> Option<&Texture> {
+        self.sky_texture_index.map(|index| self.texture_at_index(index))
+    }
+    pub fn get_entity_texture(&self, entity: &Entity) -> &Texture {
+        self.texture_at_index(entity.texture_index)
+    }
+    /// Recomputes the lightmap from `lights`, has to be called after changing them.
+    pub fn bake_lightmap(&mut self) {
+        self.lightmap = Lightmap::bake(self, &self.lights);
+    }
+    /// Light on the floor, ceiling or an entity at `point`.
+    pub fn get_light(&self, point: &Point) -> LightLevel {
+        self.dynamic_light(point).add(self.lightmap.floor_light(point))
+    }
+    /// Light on the face of a wall, `point` being where on the face.
+    pub fn get_face_light(&self, tile: (i32, i32), face: Face, point: &Point) -> LightLevel {
+        self.dynamic_light(point).add(self.lightmap.face_light(tile, face))
+    }
+    fn dynamic_light(&self, point: &Point) -> LightLevel {
+        self.dynamic_lights
+            .iter()
+            .fold(LightLevel::default(), |total, light| total.add(light.light_at(point)))
+    }
+    pub fn get_door(&self, tile: (i32, i32)) -> Option<&Door> {
+        self.doors.get(&tile)
+    }
+    /// Whether the point can't be walked into, taking open doors into account.
+    pub fn is_solid(&self, point: &Point) -> bool {
+        match self.doors.get(&(point.x.floor() as i32, point.y.floor() as i32)) {
+            Some(door) => door.open_amount < DOOR_PASSABLE_AMOUNT,
+            None => self.get_tile(point).solid,
+        }
+    }
+    /// Opens or closes the nearest door in front of `pos`, if there is one within reach.
+    pub fn use_door(&mut self, pos: &Point, rotation: &Rotation) {
+        let ray_dir: (f32, f32) = (rotation.degree.to_radians().cos(), rotation.degree.to_radians().sin());
+        let standing_in = (pos.x.floor() as i32, pos.y.floor() as i32);
+
+        for step in 1..=((DOOR_USE_DISTANCE * 4.0) as usize) {
+            let distance = step as f32 / 4.0;
+            let tile = (
+                (pos.x + ray_dir.0 * distance).floor() as i32,
+                (pos.y + ray_dir.1 * distance).floor() as i32,
+            );
+            if let Some(door) = self.doors.get_mut(&tile) {
+                // Don't let the door close on whoever is standing in it
+                if tile != standing_in || !door.opening {
+                    door.opening = !door.opening;
+                }
+                return;
+            }
+            if !self.get_tile(&Point::new(tile.0 as f32, tile.1 as f32)).transparent {
+                return;
+            }
+        }
+    }
+    /// Advances door and texture animations by `elapsed_seconds`.
+    pub fn update(&mut self, elapsed_seconds: f32) {
+        self.time += elapsed_seconds;
+        for door in self.doors.values_mut() {
+            door.open_amount = if door.opening {
+                (door.open_amount + DOOR_SPEED * elapsed_seconds).min(1.0)
+            } else {
+                (door.open_amount - DOOR_SPEED * elapsed_seconds).max(0.0)
+            };
+        }
+    }
+    pub fn is_in_level(&self, point: &Point) -> bool {
+        !((point.x < 0.0 || point.x > self.width as f32) || (point.y < 0.0 || point.y > self.height as f32))
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct Tile {
+    pub solid: bool,
+    pub transparent: bool,
+    pub texture_index: u8,
+    /// Door tiles are drawn as a thin panel through the middle of the cell that slides open.
+    pub door: bool,
+    /// How tall the wall is, 1.0 being as tall as the ceiling is high.
+    pub height: f32,
+    /// How far above the floor the bottom of the wall is.
+    pub elevation: f32,
+    pub floor_texture_index: Option<u8>,
+    pub ceiling_texture_index: Option<u8>,
+    pub north_texture_index: Option<u8>,
+    pub south_texture_index: Option<u8>,
+    pub east_texture_index: Option<u8>,
+    pub west_texture_index: Option<u8>,
+}
+
+impl Tile {
+    pub fn new(texture_index: u8, solid: bool, transparent: bool) -> Tile {
+        Tile {
+            texture_index,
+            solid,
+            transparent,
+            door: false,
+            height: 1.0,
+            elevation: 0.0,
+            floor_texture_index: None,
+            ceiling_texture_index: None,
+            north_texture_index: None,
+            south_texture_index: None,
+            east_texture_index: None,
+            west_texture_index: None,
+        }
+    }
+    pub fn top(&self) -> f32 {
+        self.elevation + self.height
+    }
+    /// The texture of one face, falling back to `texture_index` if the face doesn't set its own.
+    pub fn face_texture_index(&self, face: Face) -> u8 {
+        match face {
+            Face::North => self.north_texture_index,
+            Face::South => self.south_texture_index,
+            Face::East => self.east_texture_index,
+            Face::West => self.west_texture_index,
+        }
+        .unwrap_or(self.texture_index)
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct Spawn {
+    pub pos: Point,
+    pub rotation: Rotation,
+}
+
+// --------------------------------------------------------------------------------
+
+/// An object drawn as a billboard that always faces the camera, standing on the floor.
+#[derive(Debug, Clone)]
+pub struct Entity {
+    pub pos: Point,
+    pub texture_index: u8,
+    /// Height relative to a wall.
+    pub scale: f32,
+    /// Entities with a facing use a texture holding `DIRECTIONAL_FRAMES` frames side by side, seen
+    /// from the front and then every 45 degrees clockwise around the entity.
+    pub facing: Option<Rotation>,
+}
+
+pub const DIRECTIONAL_FRAMES: usize = 8;
+
+impl Entity {
+    pub fn new(pos: Point, texture_index: u8) -> Entity {
+        Entity {
+            pos,
+            texture_index,
+            scale: 1.0,
+            facing: None,
+        }
+    }
+    pub fn frame_count(&self) -> usize {
+        if self.facing.is_some() {
+            DIRECTIONAL_FRAMES
+        } else {
+            1
+        }
+    }
+    /// Which frame to draw when seen by a camera looking in `view_rotation`.
+    pub fn frame(&self, view_rotation: &Rotation) -> usize {
+        match &self.facing {
+            // Looking the opposite way to the entity's facing sees it from the front
+            Some(facing) => {
+                let seen_from: f32 = clamp_degrees(view_rotation.degree - facing.degree - 180.0);
+                ((seen_from / (360.0 / DIRECTIONAL_FRAMES as f32)).round() as usize) % DIRECTIONAL_FRAMES
+            }
+            None => 0,
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct Door {
+    /// Whether the panel runs along the x axis, so the door is walked through north to south.
+    pub horizontal: bool,
+    /// 0.0 when closed, 1.0 when fully open.
+    pub open_amount: f32,
+    pub opening: bool,
+}
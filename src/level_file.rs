@@ -0,0 +1,504 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::color::Color;
+use crate::level::{Entity, Level, Spawn, Tile, DIRECTIONAL_FRAMES};
+use crate::light::{Atmosphere, FaceShading, FogFalloff, Light};
+use crate::math::{Point, Rotation};
+use crate::texture::{Animation, Texture, TextureError, TextureRegistry};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelErrorKind {
+    Syntax(String),
+    EmptyGrid,
+    RaggedRow { expected: usize, found: usize },
+    UnknownTileIndex { index: u8, tile_count: usize },
+    UnknownTexture(String),
+    NoTiles,
+    TooManyTiles(usize),
+    TooManyTextures,
+    InvalidAnimation { name: String, reason: &'static str },
+    InvalidDirectionalTexture { texture: String, width: usize },
+}
+
+/// An error found while loading a level file, with the 1-based line and column it was found at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelError {
+    pub kind: LevelErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl LevelError {
+    fn new(kind: LevelErrorKind, source: &str, span: Range<usize>) -> LevelError {
+        let (line, column) = line_and_column(source, span.start);
+        LevelError { kind, line, column }
+    }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            LevelErrorKind::Syntax(message) => write!(f, "{}", message),
+            LevelErrorKind::EmptyGrid => write!(f, "the grid has no tiles"),
+            LevelErrorKind::RaggedRow { expected, found } => {
+                write!(f, "row has {} tiles, expected {} like the first row", found, expected)
+            }
+            LevelErrorKind::UnknownTileIndex { index, tile_count } => {
+                write!(
+                    f,
+                    "unknown tile index {}, the level only defines {} tiles",
+                    index, tile_count
+                )
+            }
+            LevelErrorKind::UnknownTexture(name) => write!(f, "unknown texture {:?}", name),
+            LevelErrorKind::NoTiles => write!(f, "the level defines no tiles"),
+            LevelErrorKind::TooManyTiles(count) => {
+                write!(f, "the level defines {} tiles, at most 256 are allowed", count)
+            }
+            LevelErrorKind::TooManyTextures => write!(f, "the level uses more than 256 textures"),
+            LevelErrorKind::InvalidAnimation { name, reason } => write!(f, "animation {:?}: {}", name, reason),
+            LevelErrorKind::InvalidDirectionalTexture { texture, width } => write!(
+                f,
+                "texture {:?} is {} pixels wide, entities with a facing need a non-zero multiple of {}",
+                texture, width, DIRECTIONAL_FRAMES
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+// --------------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LevelFile {
+    grid: Spanned<Vec<Spanned<Vec<Spanned<u8>>>>>,
+    spawn: SpawnFile,
+    sky: Option<Spanned<String>>,
+    #[serde(default)]
+    textures: BTreeMap<String, String>,
+    #[serde(default)]
+    animations: BTreeMap<Spanned<String>, AnimationFile>,
+    tiles: Spanned<Vec<TileFile>>,
+    #[serde(default)]
+    entities: Vec<Spanned<EntityFile>>,
+    #[serde(default)]
+    lights: Vec<LightFile>,
+    #[serde(default)]
+    atmosphere: AtmosphereFile,
+    face_shading: Option<FaceShadingFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpawnFile {
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TileFile {
+    texture: Spanned<String>,
+    solid: bool,
+    transparent: bool,
+    #[serde(default)]
+    door: bool,
+    #[serde(default = "default_one")]
+    height: f32,
+    #[serde(default)]
+    elevation: f32,
+    floor: Option<Spanned<String>>,
+    ceiling: Option<Spanned<String>>,
+    north: Option<Spanned<String>>,
+    south: Option<Spanned<String>>,
+    east: Option<Spanned<String>>,
+    west: Option<Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationFile {
+    frames: Option<Vec<Spanned<String>>>,
+    sheet: Option<Spanned<String>>,
+    frame_count: Option<usize>,
+    durations: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EntityFile {
+    x: f32,
+    y: f32,
+    texture: Spanned<String>,
+    #[serde(default = "default_one")]
+    scale: f32,
+    facing: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightFile {
+    x: f32,
+    y: f32,
+    color: [u8; 3],
+    radius: f32,
+    #[serde(default = "default_one")]
+    intensity: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, default)]
+struct AtmosphereFile {
+    fog_color: [u8; 3],
+    fog_start: f32,
+    fog_end: f32,
+    falloff: FogFalloffFile,
+    ambient: f32,
+}
+
+impl Default for AtmosphereFile {
+    fn default() -> AtmosphereFile {
+        let atmosphere = Atmosphere::default();
+        AtmosphereFile {
+            fog_color: [atmosphere.fog_color.r, atmosphere.fog_color.g, atmosphere.fog_color.b],
+            fog_start: atmosphere.fog_start,
+            fog_end: atmosphere.fog_end,
+            falloff: FogFalloffFile::InverseDistance,
+            ambient: atmosphere.ambient,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FogFalloffFile {
+    InverseDistance,
+    Linear,
+    Exponential,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FaceShadingFile {
+    #[serde(default = "default_one")]
+    north: f32,
+    #[serde(default = "default_one")]
+    south: f32,
+    #[serde(default = "default_one")]
+    east: f32,
+    #[serde(default = "default_one")]
+    west: f32,
+    sun: Option<f32>,
+    #[serde(default = "default_sun_ambient")]
+    ambient: f32,
+}
+
+fn default_sun_ambient() -> f32 {
+    0.5
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+fn parse(source: &str) -> Result<LevelFile, LevelError> {
+    toml::from_str(source).map_err(|e| {
+        LevelError::new(
+            LevelErrorKind::Syntax(e.message().to_string()),
+            source,
+            e.span().unwrap_or(0..0),
+        )
+    })
+}
+
+impl Level {
+    /// Loads a level from its TOML description using only the builtin textures, see
+    /// `levels/demo.toml` for the format.
+    pub fn from_toml(source: &str) -> Result<Level, LevelError> {
+        Level::from_toml_with_textures(source, &TextureRegistry::builtin())
+    }
+    /// The `(name, path)` pairs of the `[textures]` table, which the caller should load into the
+    /// registry passed to `from_toml_with_textures`.
+    pub fn texture_paths(source: &str) -> Result<Vec<(String, String)>, LevelError> {
+        Ok(parse(source)?.textures.into_iter().collect())
+    }
+    pub fn from_toml_with_textures(source: &str, textures: &TextureRegistry) -> Result<Level, LevelError> {
+        let file = parse(source)?;
+
+        if file.tiles.get_ref().is_empty() {
+            return Err(LevelError::new(LevelErrorKind::NoTiles, source, file.tiles.span()));
+        }
+        if file.tiles.get_ref().len() > 256 {
+            return Err(LevelError::new(
+                LevelErrorKind::TooManyTiles(file.tiles.get_ref().len()),
+                source,
+                file.tiles.span(),
+            ));
+        }
+
+        let mut level_textures = LevelTextures {
+            registry: textures,
+            source,
+            all_textures: vec![],
+            indices: HashMap::new(),
+        };
+        let mut animations: Vec<(u8, Animation)> = vec![];
+        for (name, animation) in &file.animations {
+            animations.push(level_textures.add_animation(name, animation)?);
+        }
+        let mut texture_index = |name: &Spanned<String>| level_textures.index(name);
+        let mut all_tiles: Vec<Tile> = vec![];
+        for tile in file.tiles.get_ref() {
+            let mut new_tile = Tile::new(texture_index(&tile.texture)?, tile.solid, tile.transparent);
+            new_tile.door = tile.door;
+            new_tile.height = tile.height;
+            new_tile.elevation = tile.elevation;
+            if let Some(floor) = &tile.floor {
+                new_tile.floor_texture_index = Some(texture_index(floor)?);
+            }
+            if let Some(ceiling) = &tile.ceiling {
+                new_tile.ceiling_texture_index = Some(texture_index(ceiling)?);
+            }
+            if let Some(north) = &tile.north {
+                new_tile.north_texture_index = Some(texture_index(north)?);
+            }
+            if let Some(south) = &tile.south {
+                new_tile.south_texture_index = Some(texture_index(south)?);
+            }
+            if let Some(east) = &tile.east {
+                new_tile.east_texture_index = Some(texture_index(east)?);
+            }
+            if let Some(west) = &tile.west {
+                new_tile.west_texture_index = Some(texture_index(west)?);
+            }
+            all_tiles.push(new_tile);
+        }
+
+        let mut entities: Vec<Entity> = vec![];
+        for entity in file.entities.iter().map(Spanned::get_ref) {
+            let mut new_entity = Entity::new(Point::new(entity.x, entity.y), texture_index(&entity.texture)?);
+            new_entity.scale = entity.scale;
+            new_entity.facing = entity.facing.map(Rotation::new);
+            entities.push(new_entity);
+        }
+
+        let sky_texture_index: Option<u8> = match &file.sky {
+            Some(sky) => Some(texture_index(sky)?),
+            None => None,
+        };
+
+        // Directional entities are cut into one frame per direction, for every frame of an animation
+        for (entity, new_entity) in file.entities.iter().zip(&entities) {
+            if new_entity.facing.is_none() {
+                continue;
+            }
+            let frames: Vec<u8> = match animations.iter().find(|(index, _)| *index == new_entity.texture_index) {
+                Some((_, animation)) => animation.frames().to_vec(),
+                None => vec![new_entity.texture_index],
+            };
+            for frame in frames {
+                let width: usize = level_textures.all_textures[frame as usize].width;
+                if width == 0 || !width.is_multiple_of(DIRECTIONAL_FRAMES) {
+                    return Err(LevelError::new(
+                        LevelErrorKind::InvalidDirectionalTexture {
+                            texture: entity.get_ref().texture.get_ref().clone(),
+                            width,
+                        },
+                        source,
+                        entity.span(),
+                    ));
+                }
+            }
+        }
+
+        let rows = file.grid.get_ref();
+        if rows.is_empty() || rows[0].get_ref().is_empty() {
+            return Err(LevelError::new(LevelErrorKind::EmptyGrid, source, file.grid.span()));
+        }
+        let width = rows[0].get_ref().len();
+        let mut layout: Vec<Vec<u8>> = vec![];
+        for row in rows {
+            if row.get_ref().len() != width {
+                return Err(LevelError::new(
+                    LevelErrorKind::RaggedRow {
+                        expected: width,
+                        found: row.get_ref().len(),
+                    },
+                    source,
+                    row.span(),
+                ));
+            }
+            for index in row.get_ref() {
+                if *index.get_ref() as usize >= all_tiles.len() {
+                    return Err(LevelError::new(
+                        LevelErrorKind::UnknownTileIndex {
+                            index: *index.get_ref(),
+                            tile_count: all_tiles.len(),
+                        },
+                        source,
+                        index.span(),
+                    ));
+                }
+            }
+            layout.push(row.get_ref().iter().map(|index| *index.get_ref()).collect());
+        }
+
+        let mut level = Level::new(layout, all_tiles, level_textures.all_textures);
+        for (texture_index, animation) in animations {
+            level.set_animation(texture_index, animation);
+        }
+        level.spawn = Spawn {
+            pos: Point::new(file.spawn.x, file.spawn.y),
+            rotation: Rotation::new(file.spawn.rotation),
+        };
+        level.entities = entities;
+        level.sky_texture_index = sky_texture_index;
+        level.lights = file
+            .lights
+            .iter()
+            .map(|light| Light {
+                pos: Point::new(light.x, light.y),
+                color: Color::new(light.color[0], light.color[1], light.color[2]),
+                radius: light.radius,
+                intensity: light.intensity,
+            })
+            .collect();
+        level.bake_lightmap();
+        level.atmosphere = Atmosphere {
+            fog_color: Color::new(
+                file.atmosphere.fog_color[0],
+                file.atmosphere.fog_color[1],
+                file.atmosphere.fog_color[2],
+            ),
+            fog_start: file.atmosphere.fog_start,
+            fog_end: file.atmosphere.fog_end,
+            falloff: match file.atmosphere.falloff {
+                FogFalloffFile::InverseDistance => FogFalloff::InverseDistance,
+                FogFalloffFile::Linear => FogFalloff::Linear,
+                FogFalloffFile::Exponential => FogFalloff::Exponential,
+            },
+            ambient: file.atmosphere.ambient,
+        };
+        level.face_shading = match file.face_shading {
+            // A sun replaces the per face values
+            Some(FaceShadingFile {
+                sun: Some(sun),
+                ambient,
+                ..
+            }) => FaceShading::from_sun(&Rotation::new(sun), ambient),
+            Some(face_shading) => FaceShading {
+                north: face_shading.north,
+                south: face_shading.south,
+                east: face_shading.east,
+                west: face_shading.west,
+            },
+            None => FaceShading::default(),
+        };
+        Ok(level)
+    }
+}
+
+// The textures a level uses, in the order their indices refer to
+struct LevelTextures<'a> {
+    registry: &'a TextureRegistry,
+    source: &'a str,
+    all_textures: Vec<Texture>,
+    indices: HashMap<String, u8>,
+}
+
+impl LevelTextures<'_> {
+    fn push(&mut self, texture: Texture, span: Range<usize>) -> Result<u8, LevelError> {
+        if self.all_textures.len() == 256 {
+            return Err(LevelError::new(LevelErrorKind::TooManyTextures, self.source, span));
+        }
+        self.all_textures.push(texture);
+        Ok((self.all_textures.len() - 1) as u8)
+    }
+    fn get(&self, name: &Spanned<String>) -> Result<&Texture, LevelError> {
+        self.registry.get(name.get_ref()).ok_or_else(|| {
+            LevelError::new(
+                LevelErrorKind::UnknownTexture(name.get_ref().clone()),
+                self.source,
+                name.span(),
+            )
+        })
+    }
+    fn index(&mut self, name: &Spanned<String>) -> Result<u8, LevelError> {
+        if let Some(index) = self.indices.get(name.get_ref()) {
+            return Ok(*index);
+        }
+        let index = self.push(self.get(name)?.clone(), name.span())?;
+        self.indices.insert(name.get_ref().clone(), index);
+        Ok(index)
+    }
+    // Adds the animation's frames and the texture tiles refer to it by, which starts as its first frame
+    fn add_animation(
+        &mut self,
+        name: &Spanned<String>,
+        animation: &AnimationFile,
+    ) -> Result<(u8, Animation), LevelError> {
+        let invalid = |reason: &'static str| {
+            LevelError::new(
+                LevelErrorKind::InvalidAnimation {
+                    name: name.get_ref().clone(),
+                    reason,
+                },
+                self.source,
+                name.span(),
+            )
+        };
+
+        let frames: Vec<u8> = match (&animation.frames, &animation.sheet, animation.frame_count) {
+            (Some(frames), None, None) if !frames.is_empty() => {
+                frames.iter().map(|frame| self.index(frame)).collect::<Result<_, _>>()?
+            }
+            (None, Some(sheet), Some(frame_count)) if frame_count > 0 => {
+                let sheet_texture = self.get(sheet)?;
+                if sheet_texture.width % frame_count != 0 {
+                    return Err(invalid("the sheet's width isn't a multiple of frame_count"));
+                }
+                sheet_texture
+                    .split_frames(frame_count)
+                    .into_iter()
+                    .map(|frame| self.push(frame, sheet.span()))
+                    .collect::<Result<_, _>>()?
+            }
+            _ => {
+                return Err(invalid(
+                    "needs either a non-empty `frames` list, or a `sheet` and `frame_count`",
+                ))
+            }
+        };
+        let durations: Vec<f32> = match animation.durations.len() {
+            1 => vec![animation.durations[0]; frames.len()],
+            count if count == frames.len() => animation.durations.clone(),
+            _ => return Err(invalid("`durations` needs one entry, or one per frame")),
+        };
+        let first_frame: usize = frames[0] as usize;
+        let animation = Animation::new(frames, durations).map_err(|e| match e {
+            TextureError::InvalidAnimation(reason) => invalid(reason),
+            _ => unreachable!("Animation::new only fails with InvalidAnimation"),
+        })?;
+
+        let index = self.push(self.all_textures[first_frame].clone(), name.span())?;
+        self.indices.insert(name.get_ref().clone(), index);
+        Ok((index, animation))
+    }
+}
@@ -0,0 +1,278 @@
+use crate::color::Color;
+use crate::level::Level;
+use crate::math::{Point, Rotation};
+use crate::ray::{cast_ray, Face};
+
+/// Red, green and blue light falling on a surface, 1.0 adds the surface's full unshaded color.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LightLevel {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl LightLevel {
+    pub fn new(r: f32, g: f32, b: f32) -> LightLevel {
+        LightLevel { r, g, b }
+    }
+    pub fn add(&self, other: LightLevel) -> LightLevel {
+        LightLevel::new(self.r + other.r, self.g + other.g, self.b + other.b)
+    }
+    pub fn scale(&self, factor: f32) -> LightLevel {
+        LightLevel::new(self.r * factor, self.g * factor, self.b * factor)
+    }
+    pub fn is_dark(&self) -> bool {
+        self.r <= 0.0 && self.g <= 0.0 && self.b <= 0.0
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FogFalloff {
+    /// Brightness is one over the distance past `fog_start`, ignores `fog_end`.
+    InverseDistance,
+    /// Fog thickens evenly from `fog_start` until it hides everything at `fog_end`.
+    Linear,
+    /// Fog thickens quickly past `fog_start` and then slowly, hiding 95% at `fog_end`.
+    Exponential,
+}
+
+/// How surfaces fade into fog with distance, set per level.
+#[derive(Debug, Clone)]
+pub struct Atmosphere {
+    pub fog_color: Color,
+    pub fog_start: f32,
+    pub fog_end: f32,
+    pub falloff: FogFalloff,
+    /// The lowest brightness fog can shade a surface to, from 0.0 to 1.0.
+    pub ambient: f32,
+}
+
+impl Default for Atmosphere {
+    fn default() -> Atmosphere {
+        Atmosphere {
+            fog_color: Color::new(0, 0, 0),
+            fog_start: 0.0,
+            fog_end: 16.0,
+            falloff: FogFalloff::InverseDistance,
+            ambient: 1.0 / 16.0,
+        }
+    }
+}
+
+impl Atmosphere {
+    /// How much of a surface's own color is left at `distance`, the rest being fog.
+    pub fn brightness(&self, distance: f32) -> f32 {
+        let fog_distance: f32 = (distance - self.fog_start).max(0.0);
+        let brightness: f32 = match self.falloff {
+            FogFalloff::InverseDistance => 1.0 / fog_distance.max(1.0),
+            FogFalloff::Linear => 1.0 - fog_distance / (self.fog_end - self.fog_start).max(0.001),
+            FogFalloff::Exponential => (-3.0 * fog_distance / (self.fog_end - self.fog_start).max(0.001)).exp(),
+        };
+        brightness.clamp(self.ambient.clamp(0.0, 1.0), 1.0)
+    }
+    /// Fades `color` into fog by `distance` and adds the light falling on it, which brightens it
+    /// even far away.
+    pub fn shade(&self, color: &Color, distance: f32, light: LightLevel) -> Color {
+        let brightness: f32 = self.brightness(distance);
+        let shade_channel = |channel: u8, fog_channel: u8, light: f32| -> u8 {
+            (channel as f32 * brightness + fog_channel as f32 * (1.0 - brightness) + channel as f32 * light).min(255.0)
+                as u8
+        };
+        Color::new_with_alpha(
+            shade_channel(color.r, self.fog_color.r, light.r),
+            shade_channel(color.g, self.fog_color.g, light.g),
+            shade_channel(color.b, self.fog_color.b, light.b),
+            color.a,
+        )
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+/// How bright each face of a wall is drawn, giving corners depth without any lights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceShading {
+    pub north: f32,
+    pub south: f32,
+    pub east: f32,
+    pub west: f32,
+}
+
+impl Default for FaceShading {
+    fn default() -> FaceShading {
+        FaceShading {
+            north: 1.0,
+            south: 1.0,
+            east: 1.0,
+            west: 1.0,
+        }
+    }
+}
+
+impl FaceShading {
+    /// Shading from a sun shining from `direction`, faces turned away from it are `ambient` bright.
+    pub fn from_sun(direction: &Rotation, ambient: f32) -> FaceShading {
+        let (sun_x, sun_y) = (direction.degree.to_radians().cos(), direction.degree.to_radians().sin());
+        let brightness = |face: Face| {
+            let (normal_x, normal_y) = face.normal();
+            ambient + (1.0 - ambient) * (normal_x as f32 * sun_x + normal_y as f32 * sun_y).max(0.0)
+        };
+        FaceShading {
+            north: brightness(Face::North),
+            south: brightness(Face::South),
+            east: brightness(Face::East),
+            west: brightness(Face::West),
+        }
+    }
+    pub fn brightness(&self, face: Face) -> f32 {
+        match face {
+            Face::North => self.north,
+            Face::South => self.south,
+            Face::East => self.east,
+            Face::West => self.west,
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+/// A point light, fading out towards its radius.
+#[derive(Debug, Clone)]
+pub struct Light {
+    pub pos: Point,
+    pub color: Color,
+    pub radius: f32,
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn new(pos: Point, color: Color, radius: f32) -> Light {
+        Light {
+            pos,
+            color,
+            radius,
+            intensity: 1.0,
+        }
+    }
+    /// The light reaching `point`, not taking walls in the way into account.
+    pub fn light_at(&self, point: &Point) -> LightLevel {
+        let distance: f32 = ((point.x - self.pos.x).powi(2) + (point.y - self.pos.y).powi(2)).sqrt();
+        if distance >= self.radius {
+            return LightLevel::default();
+        }
+        let falloff: f32 = (1.0 - distance / self.radius).powi(2) * self.intensity;
+        LightLevel::new(
+            self.color.r as f32 / 255.0 * falloff,
+            self.color.g as f32 / 255.0 * falloff,
+            self.color.b as f32 / 255.0 * falloff,
+        )
+    }
+    // Whether nothing blocks the light between it and `point`, or the face of `tile` at `point`
+    fn reaches(&self, level: &Level, point: &Point, tile: Option<((i32, i32), Face)>) -> bool {
+        let (x, y) = (point.x - self.pos.x, point.y - self.pos.y);
+        let distance: f32 = (x * x + y * y).sqrt();
+        if distance < 0.001 {
+            return true;
+        }
+        let hit = cast_ray(&self.pos, &Rotation::new(y.atan2(x).to_degrees()), level);
+        hit.distance >= distance - 0.01 || tile == Some((hit.tile, hit.face))
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+/// Light baked from a level's static lights, once for the centre of every tile and once for the
+/// centre of every face of every tile.
+#[derive(Debug, Clone, Default)]
+pub struct Lightmap {
+    width: usize,
+    height: usize,
+    tiles: Vec<LightLevel>,
+    faces: Vec<[LightLevel; 4]>,
+}
+
+const FACES: [Face; 4] = [Face::North, Face::South, Face::East, Face::West];
+
+fn face_index(face: Face) -> usize {
+    match face {
+        Face::North => 0,
+        Face::South => 1,
+        Face::East => 2,
+        Face::West => 3,
+    }
+}
+
+impl Lightmap {
+    pub fn bake(level: &Level, lights: &[Light]) -> Lightmap {
+        if lights.is_empty() {
+            return Lightmap::default();
+        }
+        let (width, height) = (level.width(), level.height());
+        let mut lightmap = Lightmap {
+            width,
+            height,
+            tiles: vec![LightLevel::default(); width * height],
+            faces: vec![[LightLevel::default(); 4]; width * height],
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let center = Point::new(x as f32 + 0.5, y as f32 + 0.5);
+                for light in lights {
+                    if light.reaches(level, &center, None) {
+                        lightmap.tiles[y * width + x] = lightmap.tiles[y * width + x].add(light.light_at(&center));
+                    }
+                    for face in FACES {
+                        let (normal_x, normal_y) = face.normal();
+                        let face_center =
+                            Point::new(center.x + normal_x as f32 * 0.5, center.y + normal_y as f32 * 0.5);
+                        if light.reaches(level, &face_center, Some(((x as i32, y as i32), face))) {
+                            let light_level = &mut lightmap.faces[y * width + x][face_index(face)];
+                            *light_level = light_level.add(light.light_at(&face_center));
+                        }
+                    }
+                }
+            }
+        }
+        lightmap
+    }
+    fn tile(&self, x: i32, y: i32) -> LightLevel {
+        let (x, y) = (
+            x.clamp(0, self.width as i32 - 1) as usize,
+            y.clamp(0, self.height as i32 - 1) as usize,
+        );
+        self.tiles[y * self.width + x]
+    }
+    /// Light on the floor or ceiling at `point`, blended between the nearest tile centres.
+    pub fn floor_light(&self, point: &Point) -> LightLevel {
+        if self.tiles.is_empty() {
+            return LightLevel::default();
+        }
+        let (x, y) = (point.x - 0.5, point.y - 0.5);
+        let (tile_x, tile_y) = (x.floor() as i32, y.floor() as i32);
+        let (fraction_x, fraction_y) = (x - x.floor(), y - y.floor());
+
+        let top = self
+            .tile(tile_x, tile_y)
+            .scale(1.0 - fraction_x)
+            .add(self.tile(tile_x + 1, tile_y).scale(fraction_x));
+        let bottom = self
+            .tile(tile_x, tile_y + 1)
+            .scale(1.0 - fraction_x)
+            .add(self.tile(tile_x + 1, tile_y + 1).scale(fraction_x));
+        top.scale(1.0 - fraction_y).add(bottom.scale(fraction_y))
+    }
+    pub fn face_light(&self, tile: (i32, i32), face: Face) -> LightLevel {
+        if self.faces.is_empty()
+            || tile.0 < 0
+            || tile.1 < 0
+            || tile.0 as usize >= self.width
+            || tile.1 as usize >= self.height
+        {
+            return LightLevel::default();
+        }
+        self.faces[tile.1 as usize * self.width + tile.0 as usize][face_index(face)]
+    }
+}
@@ -0,0 +1,40 @@
+#[derive(Debug, Clone)]
+pub struct Rotation {
+    pub degree: f32,
+}
+
+impl Rotation {
+    pub fn new(initial_value: f32) -> Rotation {
+        Rotation {
+            degree: clamp_degrees(initial_value),
+        }
+    }
+    pub fn mod_value(&mut self, value: f32) {
+        self.degree = clamp_degrees(self.degree + value);
+    }
+}
+
+pub fn clamp_degrees(value: f32) -> f32 {
+    let mut degree_temp = value;
+    while degree_temp < 0.0 {
+        degree_temp += 360.0;
+    }
+    while degree_temp > 360.0 {
+        degree_temp -= 360.0;
+    }
+    degree_temp
+}
+
+// --------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x_val: f32, y_val: f32) -> Point {
+        Point { x: x_val, y: y_val }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::level::Level;
+use crate::math::{Point, Rotation};
+
+/// The face of a tile a ray hit, named after the direction it faces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Face {
+    pub fn normal(&self) -> (i32, i32) {
+        match self {
+            Face::North => (0, -1),
+            Face::South => (0, 1),
+            Face::East => (1, 0),
+            Face::West => (-1, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RayHit {
+    pub point: Point,
+    pub tile: (i32, i32),
+    pub face: Face,
+    /// Where along the face the ray hit, from 0.0 at its left edge to 1.0 at its right edge as seen
+    /// from in front of it.
+    pub wall_offset: f32,
+    pub distance: f32,
+    /// Where the ray leaves the tile again, the far edge of the wall's top and bottom.
+    pub exit_distance: f32,
+}
+
+impl RayHit {
+    pub fn tile_point(&self) -> Point {
+        Point::new(self.tile.0 as f32, self.tile.1 as f32)
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+pub fn cast_ray(pos: &Point, rotation: &Rotation, level: &Level) -> RayHit {
+    cast_ray_layers(pos, rotation, level, 1)[0]
+}
+
+/// Casts a ray through walls whose face texture has alpha or that don't reach from the floor to
+/// the level's tallest wall, returning every wall hit from nearest to farthest, up to `max_layers`
+/// of them. The last hit is the wall that stopped the ray, unless `max_layers` was reached first.
+/// Door panels count as walls.
+pub fn cast_ray_layers(pos: &Point, rotation: &Rotation, level: &Level, max_layers: usize) -> Vec<RayHit> {
+    let mut ray = Dda::new(pos, rotation);
+    let mut hits: Vec<RayHit> = vec![];
+
+    for _ in 0..100 {
+        ray.step();
+
+        let tile_point = Point::new(ray.map_pos.0 as f32, ray.map_pos.1 as f32);
+        let hit = if level.get_tile(&tile_point).door {
+            ray.door_hit(pos, level)
+        } else if !level.get_tile(&tile_point).transparent {
+            Some(ray.hit(pos))
+        } else {
+            None
+        };
+
+        if let Some(hit) = hit {
+            hits.push(hit);
+            let tile = level.get_tile(&tile_point);
+            let hides_behind: bool = tile.elevation <= 0.0 && tile.top() >= level.max_wall_top();
+            if (hides_behind && !level.get_face_texture(&tile_point, hit.face).has_alpha) || hits.len() >= max_layers {
+                return hits;
+            }
+        }
+    }
+
+    // Nothing opaque within reach, report where the ray gave up
+    if hits.is_empty() {
+        hits.push(ray.hit(pos));
+    }
+    hits
+}
+
+// The state of a ray walking the grid one tile boundary at a time
+struct Dda {
+    ray_dir: (f32, f32),
+    map_pos: (i32, i32),
+    side_dist: (f32, f32),
+    delta_dist: (f32, f32),
+    step: (i32, i32),
+    side: u8,
+}
+
+impl Dda {
+    fn new(pos: &Point, rotation: &Rotation) -> Dda {
+        let ray_dir: (f32, f32) = (rotation.degree.to_radians().cos(), rotation.degree.to_radians().sin());
+        let map_pos: (i32, i32) = (pos.x as i32, pos.y as i32);
+        let delta_dist: (f32, f32) = ((1.0 / ray_dir.0).abs(), (1.0 / ray_dir.1).abs());
+        let mut side_dist: (f32, f32) = (0.0, 0.0);
+        let mut step: (i32, i32) = (0, 0);
+
+        if ray_dir.0 < 0.0 {
+            step.0 = -1;
+            side_dist.0 = (pos.x - map_pos.0 as f32) * delta_dist.0;
+        } else {
+            step.0 = 1;
+            side_dist.0 = (((map_pos.0 + 1) as f32) - pos.x) * delta_dist.0;
+        }
+
+        if ray_dir.1 < 0.0 {
+            step.1 = -1;
+            side_dist.1 = (pos.y - map_pos.1 as f32) * delta_dist.1;
+        } else {
+            step.1 = 1;
+            side_dist.1 = (((map_pos.1 + 1) as f32) - pos.y) * delta_dist.1;
+        }
+
+        Dda {
+            ray_dir,
+            map_pos,
+            side_dist,
+            delta_dist,
+            step,
+            side: 0,
+        }
+    }
+    fn step(&mut self) {
+        if self.side_dist.0 < self.side_dist.1 {
+            self.side_dist.0 += self.delta_dist.0;
+            self.map_pos.0 += self.step.0;
+            self.side = 0;
+        } else {
+            self.side_dist.1 += self.delta_dist.1;
+            self.map_pos.1 += self.step.1;
+            self.side = 1;
+        }
+    }
+    // Distance along the ray to where it entered the current tile
+    fn entry_distance(&self) -> f32 {
+        if self.side == 0 {
+            self.side_dist.0 - self.delta_dist.0
+        } else {
+            self.side_dist.1 - self.delta_dist.1
+        }
+    }
+    fn hit(&self, pos: &Point) -> RayHit {
+        let distance: f32 = self.entry_distance();
+        let point = Point::new(pos.x + (self.ray_dir.0 * distance), pos.y + (self.ray_dir.1 * distance));
+
+        // Faces are mapped so that textures read left to right when looking at them from outside
+        let (face, wall_offset) = match (self.side, self.step) {
+            (0, (1, _)) => (Face::West, point.y - point.y.floor()),
+            (0, _) => (Face::East, 1.0 - (point.y - point.y.floor())),
+            (_, (_, 1)) => (Face::North, 1.0 - (point.x - point.x.floor())),
+            _ => (Face::South, point.x - point.x.floor()),
+        };
+
+        RayHit {
+            point,
+            tile: self.map_pos,
+            face,
+            wall_offset,
+            distance: distance.max(0.0001),
+            exit_distance: self.side_dist.0.min(self.side_dist.1),
+        }
+    }
+    // Door panels sit halfway into their tile and slide towards +x / +y as they open
+    fn door_hit(&self, pos: &Point, level: &Level) -> Option<RayHit> {
+        let door = level.get_door(self.map_pos)?;
+        let distance: f32 = if door.horizontal {
+            (self.map_pos.1 as f32 + 0.5 - pos.y) / self.ray_dir.1
+        } else {
+            (self.map_pos.0 as f32 + 0.5 - pos.x) / self.ray_dir.0
+        };
+        if !distance.is_finite()
+            || distance < self.entry_distance()
+            || distance >= self.side_dist.0.min(self.side_dist.1)
+        {
+            return None;
+        }
+
+        let point = Point::new(pos.x + (self.ray_dir.0 * distance), pos.y + (self.ray_dir.1 * distance));
+        let along: f32 = if door.horizontal {
+            point.x - point.x.floor()
+        } else {
+            point.y - point.y.floor()
+        };
+        if along < door.open_amount {
+            return None;
+        }
+
+        let panel_offset: f32 = along - door.open_amount;
+        let (face, wall_offset) = match (door.horizontal, self.ray_dir.0 > 0.0, self.ray_dir.1 > 0.0) {
+            (true, _, true) => (Face::North, 1.0 - panel_offset),
+            (true, _, false) => (Face::South, panel_offset),
+            (false, true, _) => (Face::West, panel_offset),
+            (false, false, _) => (Face::East, 1.0 - panel_offset),
+        };
+
+        Some(RayHit {
+            point,
+            tile: self.map_pos,
+            face,
+            wall_offset,
+            distance: distance.max(0.0001),
+            exit_distance: distance.max(0.0001),
+        })
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::color::Color;
+use crate::math::Point;
+
+#[derive(Debug)]
+pub enum TextureError {
+    Decode(image::ImageError),
+    InvalidAtlas(&'static str),
+    InvalidAnimation(&'static str),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextureError::Decode(e) => write!(f, "couldn't decode texture: {}", e),
+            TextureError::InvalidAtlas(reason) => write!(f, "invalid texture atlas: {}", reason),
+            TextureError::InvalidAnimation(reason) => write!(f, "invalid animation: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+// --------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub layout: Vec<Color>,
+    /// Whether any pixel is less than fully opaque, walls with such a texture can be seen through.
+    pub has_alpha: bool,
+}
+
+impl Texture {
+    pub fn new(width: usize, height: usize, layout: Vec<Color>) -> Texture {
+        Texture {
+            width,
+            height,
+            has_alpha: layout.iter().any(|color| color.a < 255),
+            layout,
+        }
+    }
+    pub fn blank() -> Texture {
+        Texture::new(1, 1, vec![Color::new(0, 0, 0)])
+    }
+    /// Decodes a PNG or JPEG image into a texture.
+    pub fn from_image_bytes(bytes: &[u8]) -> Result<Texture, TextureError> {
+        let image = image::load_from_memory(bytes).map_err(TextureError::Decode)?.to_rgba8();
+        Ok(Texture::new(
+            image.width() as usize,
+            image.height() as usize,
+            vec_u8_to_vec_color(image.as_raw()),
+        ))
+    }
+    /// Splits a texture holding `count` equally wide frames side by side into one texture per frame.
+    /// Panics unless the width is a non-zero multiple of `count`, so no columns are dropped.
+    pub fn split_frames(&self, count: usize) -> Vec<Texture> {
+        assert!(
+            count > 0 && self.width.is_multiple_of(count),
+            "a texture {} pixels wide can't be split into {} frames",
+            self.width,
+            count
+        );
+        let frame_width: usize = self.width / count;
+        (0..count)
+            .map(|frame| {
+                Texture::new(
+                    frame_width,
+                    self.height,
+                    self.layout
+                        .chunks(self.width)
+                        .flat_map(|row| &row[frame * frame_width..(frame + 1) * frame_width])
+                        .cloned()
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+    pub fn get_color(&self, point: &Point) -> &Color {
+        if (point.x >= 0.0 && point.x < (self.width as f32)) && (point.y >= 0.0 && point.y < (self.height as f32)) {
+            &self.layout[(self.width * (point.y as usize)) + (point.x as usize)]
+        } else {
+            &self.layout[0]
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+/// A texture that cycles through other textures of a level, each shown for its own number of seconds.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<u8>,
+    durations: Vec<f32>,
+}
+
+impl Animation {
+    /// An animation showing the texture indices in `frames` for the matching number of seconds in
+    /// `durations`, which needs one entry per frame, each greater than 0.
+    pub fn new(frames: Vec<u8>, durations: Vec<f32>) -> Result<Animation, TextureError> {
+        if frames.is_empty() {
+            return Err(TextureError::InvalidAnimation("needs at least one frame"));
+        }
+        if durations.len() != frames.len() {
+            return Err(TextureError::InvalidAnimation("needs one duration per frame"));
+        }
+        if durations.iter().any(|duration| duration.is_nan() || *duration <= 0.0) {
+            return Err(TextureError::InvalidAnimation(
+                "every duration has to be greater than 0",
+            ));
+        }
+        Ok(Animation { frames, durations })
+    }
+    pub fn frames(&self) -> &[u8] {
+        &self.frames
+    }
+    /// The texture index of the frame shown `time` seconds after the animation started, looping.
+    pub fn frame_at(&self, time: f32) -> u8 {
+        let total: f32 = self.durations.iter().sum();
+        let mut time_left: f32 = if total > 0.0 { time.rem_euclid(total) } else { 0.0 };
+        for (frame, duration) in self.frames.iter().zip(&self.durations) {
+            if time_left < *duration {
+                return *frame;
+            }
+            time_left -= duration;
+        }
+        *self.frames.last().unwrap()
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+/// Textures available to levels, keyed by the name tiles refer to them by.
+#[derive(Debug, Clone, Default)]
+pub struct TextureRegistry {
+    textures: HashMap<String, Texture>,
+}
+
+impl TextureRegistry {
+    pub fn new() -> TextureRegistry {
+        TextureRegistry::default()
+    }
+    /// A registry holding the textures bundled with the crate.
+    pub fn builtin() -> TextureRegistry {
+        let mut registry = TextureRegistry::new();
+        registry.insert("blank", Texture::blank());
+        for (name, bytes) in [
+            ("brick_wall", &include_bytes!("../textures/brick_wall.jpeg")[..]),
+            ("richardo", &include_bytes!("../textures/richardo.jpeg")[..]),
+            ("wood", &include_bytes!("../textures/floor_wood.jpeg")[..]),
+            ("grate", &include_bytes!("../textures/grate.png")[..]),
+            ("barrel", &include_bytes!("../textures/barrel.png")[..]),
+            ("guard", &include_bytes!("../textures/guard.png")[..]),
+            ("torch", &include_bytes!("../textures/torch.png")[..]),
+            ("sky", &include_bytes!("../textures/sky.png")[..]),
+        ] {
+            registry
+                .load(name, bytes)
+                .expect("bundled textures should be valid images");
+        }
+        registry
+    }
+    pub fn insert(&mut self, name: &str, texture: Texture) {
+        self.textures.insert(name.to_string(), texture);
+    }
+    pub fn load(&mut self, name: &str, bytes: &[u8]) -> Result<(), TextureError> {
+        self.insert(name, Texture::from_image_bytes(bytes)?);
+        Ok(())
+    }
+    /// Adds every texture of an atlas written by `texture_parser --format atlas`. Only the full size
+    /// mip level is used.
+    pub fn load_atlas(&mut self, bytes: &[u8]) -> Result<(), TextureError> {
+        let mut reader = AtlasReader { bytes };
+        if reader.take(4)? != b"RTEX" {
+            return Err(TextureError::InvalidAtlas("missing RTEX header"));
+        }
+        if reader.u32()? != 1 {
+            return Err(TextureError::InvalidAtlas("unsupported version"));
+        }
+
+        for _ in 0..reader.u32()? {
+            let name_length = u16::from_le_bytes(reader.take(2)?.try_into().unwrap()) as usize;
+            let name = std::str::from_utf8(reader.take(name_length)?)
+                .map_err(|_| TextureError::InvalidAtlas("texture name isn't utf8"))?
+                .to_string();
+            let (width, height) = (reader.u32()?, reader.u32()?);
+            if width == 0 || height == 0 {
+                return Err(TextureError::InvalidAtlas("texture has no pixels"));
+            }
+            let mip_levels = reader.u32()?;
+            if mip_levels > 32 {
+                return Err(TextureError::InvalidAtlas("too many mip levels"));
+            }
+
+            let mut layout: Vec<Color> = vec![];
+            for level in 0..mip_levels {
+                let level_size = |size: u32| size.checked_shr(level).unwrap_or(0).max(1) as usize;
+                let length = level_size(width)
+                    .checked_mul(level_size(height))
+                    .and_then(|pixels| pixels.checked_mul(4))
+                    .ok_or(TextureError::InvalidAtlas("texture is too large"))?;
+                let pixels = reader.take(length)?;
+                if level == 0 {
+                    layout = vec_u8_to_vec_color(pixels);
+                }
+            }
+            if layout.is_empty() {
+                return Err(TextureError::InvalidAtlas("texture has no mip levels"));
+            }
+            self.insert(&name, Texture::new(width as usize, height as usize, layout));
+        }
+        Ok(())
+    }
+    pub fn get(&self, name: &str) -> Option<&Texture> {
+        self.textures.get(name)
+    }
+}
+
+struct AtlasReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> AtlasReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], TextureError> {
+        if self.bytes.len() < length {
+            return Err(TextureError::InvalidAtlas("unexpected end of file"));
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+    fn u32(&mut self) -> Result<u32, TextureError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+fn vec_u8_to_vec_color(pixels: &[u8]) -> Vec<Color> {
+    c![Color::new_with_alpha(pixels[i * 4], pixels[i * 4 + 1], pixels[i * 4 + 2], pixels[i * 4 + 3]), for i in 0..(pixels.len() / 4)]
+}
@@ -0,0 +1,398 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::camera::Camera;
+use crate::frame_buffer::FrameBuffer;
+use crate::game_loop::FixedTimestep;
+use crate::input::{Action, InputState, KeyBindings};
+use crate::level::Level;
+use crate::texture::TextureRegistry;
+
+// Simulation ticks per second, independent of the display's refresh rate
+const TICKS_PER_SECOND: u32 = 60;
+// Degrees the camera turns per pixel the mouse moves
+const MOUSE_SENSITIVITY: f32 = 0.5;
+const KEY_BINDINGS_FILE: &str = "key_bindings.toml";
+const KEY_BINDINGS_STORAGE_KEY: &str = "key_bindings";
+
+struct GameState {
+    camera: Camera,
+    // The camera as of the tick before, drawn frames interpolate between the two
+    previous_camera: Camera,
+    level: Level,
+    input: InputState,
+    timestep: FixedTimestep,
+    game_running: bool,
+    pointer_should_be_locked: bool,
+}
+
+// --------------------------------------------------------------------------------
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+}
+
+macro_rules! console_log {
+    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
+}
+fn request_animation_frame(f: &Closure<dyn FnMut(f64)>) {
+    web_sys::window()
+        .expect("no global `window` exists")
+        .request_animation_frame(f.as_ref().unchecked_ref())
+        .expect("should register `requestAnimationFrame` OK");
+}
+
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, JsValue> {
+    let response: web_sys::Response = JsFuture::from(web_sys::window().unwrap().fetch_with_str(url))
+        .await?
+        .dyn_into()?;
+    if !response.ok() {
+        return Err(JsValue::from_str(&format!(
+            "Error fetching {}: {}",
+            url,
+            response.status()
+        )));
+    }
+    let buffer = JsFuture::from(response.array_buffer()?).await?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+// The default bindings, overridden by `key_bindings.toml` if it is served and then by the same
+// format in localStorage, errors are logged and leave the bindings as they were
+async fn load_key_bindings() -> KeyBindings {
+    let mut bindings = KeyBindings::default();
+    let mut apply = |origin: &str, source: &str| {
+        if let Err(e) = bindings.apply_toml(source) {
+            console_log!("Ignoring key bindings from {}: {}", origin, e);
+        }
+    };
+    if let Ok(bytes) = fetch_bytes(KEY_BINDINGS_FILE).await {
+        apply(KEY_BINDINGS_FILE, &String::from_utf8_lossy(&bytes));
+    }
+    let stored = web_sys::window()
+        .unwrap()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(KEY_BINDINGS_STORAGE_KEY).ok().flatten());
+    if let Some(source) = stored {
+        apply("localStorage", &source);
+    }
+    bindings
+}
+
+// Carries out a pressed key or gamepad button, `first_press` being false for keys repeating
+// while held
+fn perform_action(action: Action, first_press: bool, camera: &mut Camera, level: &mut Level) {
+    match action {
+        Action::UseDoor if first_press => level.use_door(&camera.pos, &camera.rotation),
+        Action::Jump if first_press => camera.jump(),
+        Action::ToggleCrouch if first_press => camera.crouching = !camera.crouching,
+        Action::ToggleFishEyeCorrection if first_press => camera.fish_eye_correction = !camera.fish_eye_correction,
+        // Fewer columns per ray is a higher resolution
+        Action::IncreaseResolution => {
+            camera.mod_columns_per_ray(-1);
+            console_log!("Columns per ray changed to: {:?}", camera.columns_per_ray);
+        }
+        Action::DecreaseResolution => {
+            camera.mod_columns_per_ray(1);
+            console_log!("Columns per ray changed to: {:?}", camera.columns_per_ray);
+        }
+        Action::DecreaseFov => {
+            camera.mod_fov(-1);
+            console_log!("FOV changed to: {:?}", camera.fov);
+        }
+        Action::IncreaseFov => {
+            camera.mod_fov(1);
+            console_log!("FOV changed to: {:?}", camera.fov);
+        }
+        _ => {}
+    }
+}
+
+// The sticks and pressed buttons of the first connected gamepad with the standard mapping
+fn poll_gamepad(navigator: &web_sys::Navigator) -> Option<(Vec<f32>, Vec<bool>)> {
+    let gamepad: web_sys::Gamepad = navigator
+        .get_gamepads()
+        .ok()?
+        .iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<web_sys::Gamepad>().ok())
+        .find(|gamepad| gamepad.connected() && gamepad.mapping() == web_sys::GamepadMappingType::Standard)?;
+    let axes: Vec<f32> = gamepad
+        .axes()
+        .iter()
+        .map(|axis| axis.as_f64().unwrap_or(0.0) as f32)
+        .collect();
+    let buttons: Vec<bool> = gamepad
+        .buttons()
+        .iter()
+        .map(|button| {
+            button
+                .dyn_into::<web_sys::GamepadButton>()
+                .map(|button| button.pressed())
+                .unwrap_or(false)
+        })
+        .collect();
+    Some((axes, buttons))
+}
+
+// --------------------------------------------------------------------------------
+
+impl FrameBuffer {
+    fn flip_to_canvas(&self, canvas: &web_sys::CanvasRenderingContext2d) {
+        match canvas.put_image_data(
+            &web_sys::ImageData::new_with_u8_clamped_array(wasm_bindgen::Clamped(self.buffer()), self.width() as u32)
+                .unwrap(),
+            0.0,
+            0.0,
+        ) {
+            Ok(_) => {}
+            Err(e) => console_log!("Error drawing FrameBuffer to canvas: {:?}", e),
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+#[wasm_bindgen(start)]
+pub async fn start() -> Result<(), JsValue> {
+    let f = Rc::new(RefCell::new(None));
+    let g = f.clone();
+
+    let window = web_sys::window().unwrap();
+    let document = window.document().unwrap();
+
+    let game_canvas_html = document
+        .get_element_by_id("game_canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .map_err(|_| ())
+        .unwrap();
+    let game_canvas = game_canvas_html
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .unwrap();
+
+    let level_source = include_str!("../levels/demo.toml");
+    let mut textures = TextureRegistry::builtin();
+    for (name, path) in Level::texture_paths(level_source).map_err(|e| JsValue::from_str(&e.to_string()))? {
+        textures
+            .load(&name, &fetch_bytes(&path).await?)
+            .map_err(|e| JsValue::from_str(&format!("{}: {}", path, e)))?;
+    }
+    let level =
+        Level::from_toml_with_textures(level_source, &textures).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let mut camera = Camera::new(level.spawn.pos);
+    camera.rotation = level.spawn.rotation.clone();
+    let game_state = Rc::new(RefCell::new(GameState {
+        previous_camera: camera.clone(),
+        camera,
+        level,
+        input: InputState::new(load_key_bindings().await),
+        timestep: FixedTimestep::new(TICKS_PER_SECOND),
+        game_running: false,
+        pointer_should_be_locked: false,
+    }));
+
+    // Keyboard input
+    {
+        let game_state = game_state.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+            let mut state = game_state.borrow_mut();
+            if state.game_running {
+                let GameState {
+                    camera, level, input, ..
+                } = &mut *state;
+                // Movement keys are held until released, the game loop moves the camera every tick
+                let action = match input.key_down(&event.code()) {
+                    Some(action) => action,
+                    None => return,
+                };
+                // Toggles and one-off actions ignore the key repeating while held
+                perform_action(action, !event.repeat(), camera, level);
+                event.prevent_default();
+            }
+        }) as Box<dyn FnMut(_)>);
+        window.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    {
+        let game_state = game_state.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+            game_state.borrow_mut().input.key_up(&event.code());
+        }) as Box<dyn FnMut(_)>);
+        window.add_event_listener_with_callback("keyup", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    // Releases go elsewhere while the page isn't focused, so nothing can be held through that
+    {
+        let game_state = game_state.clone();
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::FocusEvent| {
+            game_state.borrow_mut().input.release_all();
+        }) as Box<dyn FnMut(_)>);
+        window.add_event_listener_with_callback("blur", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    // Mouse input
+    {
+        let game_state = game_state.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            let mut state = game_state.borrow_mut();
+            if state.game_running {
+                // Applied to both cameras so looking around isn't delayed until the next tick
+                let GameState {
+                    camera,
+                    previous_camera,
+                    ..
+                } = &mut *state;
+                for camera in [camera, previous_camera] {
+                    camera.rotation.mod_value(event.movement_x() as f32 * MOUSE_SENSITIVITY);
+                    camera.mod_pitch(-(event.movement_y() as f32) * MOUSE_SENSITIVITY);
+                }
+            }
+        }) as Box<dyn FnMut(_)>);
+        game_canvas_html.add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    // Mouse click
+    {
+        let game_state = game_state.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            let mut state = game_state.borrow_mut();
+            if state.game_running {
+                state.input.mouse_down(event.button());
+            } else {
+                web_sys::window()
+                    .unwrap()
+                    .document()
+                    .unwrap()
+                    .get_element_by_id("game_canvas")
+                    .unwrap()
+                    .dyn_into::<web_sys::HtmlCanvasElement>()
+                    .map_err(|_| ())
+                    .unwrap()
+                    .request_pointer_lock();
+
+                state.game_running = true;
+                state.pointer_should_be_locked = true;
+            }
+        }) as Box<dyn FnMut(_)>);
+        game_canvas_html.add_event_listener_with_callback("mousedown", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    {
+        let game_state = game_state.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            game_state.borrow_mut().input.mouse_up(event.button());
+        }) as Box<dyn FnMut(_)>);
+        window.add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    // The right mouse button walks forward instead of opening the context menu
+    {
+        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            event.prevent_default();
+        }) as Box<dyn FnMut(_)>);
+        game_canvas_html.add_event_listener_with_callback("contextmenu", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    // Pointerlock exit
+    {
+        let game_state = game_state.clone();
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::MouseEvent| {
+            let mut state = game_state.borrow_mut();
+            if state.pointer_should_be_locked {
+                state.pointer_should_be_locked = false;
+            } else {
+                state.game_running = false;
+                state.input.release_all();
+            }
+        }) as Box<dyn FnMut(_)>);
+        document.add_event_listener_with_callback("pointerlockchange", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    // Pointerlock error
+    {
+        let game_state = game_state.clone();
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::MouseEvent| {
+            let mut state = game_state.borrow_mut();
+            state.pointer_should_be_locked = false;
+            state.game_running = false;
+        }) as Box<dyn FnMut(_)>);
+        document.add_event_listener_with_callback("pointerlockerror", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    // Game loop
+    // The timestamp requestAnimationFrame passes its callback, None until the first frame
+    let mut last_frame_time: Option<f64> = None;
+    *g.borrow_mut() = Some(Closure::wrap(Box::new(move |now: f64| {
+        {
+            // TODO: Make into event
+            let screen_width = window.inner_width().unwrap().as_f64().unwrap() as usize;
+            let screen_height = window.inner_height().unwrap().as_f64().unwrap() as usize;
+            game_canvas_html.set_width(screen_width as u32);
+            game_canvas_html.set_height(screen_height as u32);
+
+            let mut state = game_state.borrow_mut();
+            let elapsed_seconds: f32 = last_frame_time.map_or(0.0, |last| ((now - last) / 1000.0) as f32);
+            last_frame_time = Some(now);
+
+            let GameState {
+                camera,
+                previous_camera,
+                level,
+                input,
+                timestep,
+                game_running,
+                ..
+            } = &mut *state;
+            // Gamepads have no events for their sticks and buttons, so they are polled every frame.
+            // Pressing a button starts the game like clicking does, without locking the pointer
+            match poll_gamepad(&window.navigator()) {
+                Some((axes, buttons)) => {
+                    let pressed: Vec<Action> = input.update_gamepad(&axes, &buttons);
+                    if !pressed.is_empty() && !*game_running {
+                        *game_running = true;
+                    } else {
+                        for action in pressed {
+                            perform_action(action, true, camera, level);
+                        }
+                    }
+                }
+                None => {
+                    input.update_gamepad(&[], &[]);
+                }
+            }
+            let tick_seconds: f32 = timestep.tick_seconds;
+            for _ in 0..timestep.advance(elapsed_seconds) {
+                *previous_camera = camera.clone();
+                if *game_running {
+                    camera.update_from_input(level, &input.input_info(), tick_seconds);
+                }
+                camera.update_eye_height(tick_seconds);
+                level.update(tick_seconds);
+            }
+            let camera: Camera = camera.interpolate(previous_camera, timestep.alpha());
+
+            let mut frame_buffer: FrameBuffer = FrameBuffer::new(screen_width, screen_height);
+            frame_buffer.draw_floor(&camera, level);
+            frame_buffer.draw_walls(&camera, level);
+            frame_buffer.draw_sprites(&camera, level);
+            frame_buffer.draw_minimap(&camera, level);
+
+            frame_buffer.flip_to_canvas(&game_canvas);
+        }
+        request_animation_frame(f.borrow().as_ref().unwrap());
+    }) as Box<dyn FnMut(f64)>));
+
+    request_animation_frame(g.borrow().as_ref().unwrap());
+    Ok(())
+}
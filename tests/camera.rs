@@ -0,0 +1,101 @@
+use raycaster::{
+    Camera, InputInfo, Level, Point, Rotation, CROUCHING_EYE_HEIGHT, MOVEMENT_SPEED_MODIFIER, STANDING_EYE_HEIGHT,
+};
+
+fn simulate(camera: &mut Camera, seconds: f32) -> f32 {
+    let mut highest: f32 = camera.eye_height;
+    for _ in 0..(seconds * 100.0) as usize {
+        camera.update_eye_height(0.01);
+        highest = highest.max(camera.eye_height);
+    }
+    highest
+}
+
+#[test]
+fn jumps_land_back_on_the_ground() {
+    let mut camera = Camera::new(Point::new(1.5, 1.5));
+    camera.jump();
+    let highest = simulate(&mut camera, 1.0);
+    assert!(highest > STANDING_EYE_HEIGHT + 0.2);
+    assert_eq!(camera.eye_height, STANDING_EYE_HEIGHT);
+    assert_eq!(camera.vertical_speed, 0.0);
+}
+
+#[test]
+fn crouching_lowers_the_eye_height() {
+    let mut camera = Camera::new(Point::new(1.5, 1.5));
+    camera.crouching = true;
+    simulate(&mut camera, 1.0);
+    assert_eq!(camera.eye_height, CROUCHING_EYE_HEIGHT);
+
+    camera.crouching = false;
+    simulate(&mut camera, 1.0);
+    assert_eq!(camera.eye_height, STANDING_EYE_HEIGHT);
+}
+
+#[test]
+fn pitch_moves_the_horizon() {
+    let mut camera = Camera::new(Point::new(1.5, 1.5));
+    assert_eq!(camera.horizon(200), 100.0);
+    camera.mod_pitch(90.0);
+    assert_eq!(camera.pitch, 30.0);
+    assert!(camera.horizon(200) > 100.0);
+}
+
+#[test]
+fn moves_the_same_distance_at_any_tick_rate() {
+    let level = Level::demo();
+    let forward = InputInfo {
+        forward: true,
+        ..InputInfo::default()
+    };
+    for ticks_per_second in [30, 60, 144] {
+        let mut camera = Camera::new(Point::new(4.5, 7.5));
+        for _ in 0..ticks_per_second {
+            camera.update_from_input(&level, &forward, 1.0 / ticks_per_second as f32);
+        }
+        assert!((camera.pos.x - (4.5 + MOVEMENT_SPEED_MODIFIER)).abs() < 0.001);
+    }
+}
+
+#[test]
+fn moving_diagonally_is_not_faster() {
+    let level = Level::demo();
+    let mut camera = Camera::new(Point::new(6.5, 7.5));
+    camera.update_from_input(
+        &level,
+        &InputInfo {
+            forward: true,
+            right: true,
+            ..InputInfo::default()
+        },
+        0.1,
+    );
+    let moved: f32 = ((camera.pos.x - 6.5).powi(2) + (camera.pos.y - 7.5).powi(2)).sqrt();
+    assert!((moved - MOVEMENT_SPEED_MODIFIER * 0.1).abs() < 0.001);
+    assert!(camera.pos.x > 6.5 && camera.pos.y > 7.5);
+}
+
+#[test]
+fn analog_input_moves_partway() {
+    let level = Level::demo();
+    let mut camera = Camera::new(Point::new(4.5, 7.5));
+    let half_forward = InputInfo {
+        move_amount: 0.5,
+        ..InputInfo::default()
+    };
+    camera.update_from_input(&level, &half_forward, 1.0);
+    assert!((camera.pos.x - (4.5 + MOVEMENT_SPEED_MODIFIER * 0.5)).abs() < 0.001);
+}
+
+#[test]
+fn interpolates_the_short_way_round() {
+    let mut previous = Camera::new(Point::new(1.0, 1.0));
+    previous.rotation = Rotation::new(350.0);
+    let mut camera = Camera::new(Point::new(2.0, 1.0));
+    camera.rotation = Rotation::new(10.0);
+
+    let halfway = camera.interpolate(&previous, 0.5);
+    assert_eq!(halfway.pos.x, 1.5);
+    assert!(halfway.rotation.degree < 0.001 || halfway.rotation.degree > 359.999);
+}
@@ -0,0 +1,39 @@
+use raycaster::{cast_ray, Face, Level, Point, Rotation};
+
+// The demo level has a door at (6, 1) between two rooms, its panel runs north to south
+const DOOR: (i32, i32) = (6, 1);
+
+#[test]
+fn closed_door_blocks_rays_and_movement() {
+    let level = Level::demo();
+    let door = level.get_door(DOOR).unwrap();
+    assert!(!door.horizontal);
+
+    let hit = cast_ray(&Point::new(3.5, 1.5), &Rotation::new(0.0), &level);
+    assert_eq!((hit.tile, hit.face), (DOOR, Face::West));
+    assert!((hit.point.x - 6.5).abs() < 0.001);
+    assert!(level.is_solid(&Point::new(6.5, 1.5)));
+}
+
+#[test]
+fn door_slides_open_and_closed() {
+    let mut level = Level::demo();
+    level.use_door(&Point::new(5.5, 1.5), &Rotation::new(0.0));
+    level.update(0.5);
+    assert!(level.is_solid(&Point::new(6.5, 1.5)));
+    // The ray passes where the panel has slid out of the way
+    assert_ne!(cast_ray(&Point::new(5.5, 1.2), &Rotation::new(0.0), &level).tile, DOOR);
+    assert_eq!(cast_ray(&Point::new(5.5, 1.8), &Rotation::new(0.0), &level).tile, DOOR);
+
+    level.update(1.0);
+    assert_eq!(level.get_door(DOOR).unwrap().open_amount, 1.0);
+    assert!(!level.is_solid(&Point::new(6.5, 1.5)));
+
+    // Doors don't close on whoever is standing in them
+    level.use_door(&Point::new(6.5, 1.5), &Rotation::new(0.0));
+    assert!(level.get_door(DOOR).unwrap().opening);
+
+    level.use_door(&Point::new(5.5, 1.5), &Rotation::new(0.0));
+    level.update(2.0);
+    assert_eq!(level.get_door(DOOR).unwrap().open_amount, 0.0);
+}
@@ -0,0 +1,43 @@
+use raycaster::{Camera, Color, Entity, FrameBuffer, Level, Point, Rotation, Texture, Tile, DIRECTIONAL_FRAMES};
+
+#[test]
+fn directional_entity_frames_follow_the_view() {
+    let mut entity = Entity::new(Point::new(1.5, 1.5), 0);
+    assert_eq!(entity.frame(&Rotation::new(123.0)), 0);
+
+    entity.facing = Some(Rotation::new(90.0));
+    assert_eq!(entity.frame_count(), DIRECTIONAL_FRAMES);
+    // Facing each other shows the front, looking the same way shows the back
+    assert_eq!(entity.frame(&Rotation::new(270.0)), 0);
+    assert_eq!(entity.frame(&Rotation::new(90.0)), 4);
+    assert_eq!(entity.frame(&Rotation::new(315.0)), 1);
+    assert_eq!(entity.frame(&Rotation::new(180.0)), 6);
+    // Frames switch halfway between directions
+    assert_eq!(entity.frame(&Rotation::new(290.0)), 0);
+    assert_eq!(entity.frame(&Rotation::new(295.0)), 1);
+}
+
+#[test]
+fn demo_guard_uses_a_sprite_sheet() {
+    let level = Level::demo();
+    let guard = level.entities.iter().find(|entity| entity.facing.is_some()).unwrap();
+    let texture = level.get_entity_texture(guard);
+    assert_eq!(texture.width, texture.height * DIRECTIONAL_FRAMES);
+}
+
+#[test]
+fn directional_entity_narrower_than_its_frames_is_skipped() {
+    let mut level = Level::new(
+        vec![vec![0; 3]; 3],
+        vec![Tile::new(0, false, true)],
+        vec![Texture::new(1, 1, vec![Color::new(255, 255, 255)])],
+    );
+    let mut entity = Entity::new(Point::new(2.5, 1.5), 0);
+    entity.facing = Some(Rotation::new(0.0));
+    level.entities.push(entity);
+
+    let mut frame_buffer = FrameBuffer::new(32, 20);
+    let before: Vec<u8> = frame_buffer.buffer().to_vec();
+    frame_buffer.draw_sprites(&Camera::new(Point::new(0.5, 1.5)), &level);
+    assert_eq!(frame_buffer.buffer(), &before[..]);
+}
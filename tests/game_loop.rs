@@ -0,0 +1,19 @@
+use raycaster::FixedTimestep;
+
+#[test]
+fn splits_frames_into_fixed_ticks() {
+    let mut timestep = FixedTimestep::new(60);
+    assert_eq!(timestep.advance(1.0 / 144.0), 0);
+    assert!(timestep.alpha() > 0.4 && timestep.alpha() < 0.42);
+
+    // Leftover time carries over into the next frame
+    let ticks: usize = (0..144).map(|_| timestep.advance(1.0 / 144.0)).sum();
+    assert_eq!(ticks, 60);
+}
+
+#[test]
+fn long_frames_are_capped() {
+    let mut timestep = FixedTimestep::new(60);
+    let ticks = timestep.advance(10.0);
+    assert!((14..=15).contains(&ticks));
+}
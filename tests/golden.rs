@@ -0,0 +1,241 @@
+//! Renders fixed camera poses in `tests/golden/level.toml` and compares them against the reference
+//! images next to it. Run with `UPDATE_GOLDEN=1` to (re)generate the references after an intended
+//! change to the renderer's output. On failure a diff image is written next to the test binaries.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+use raycaster::{Camera, FrameBuffer, Level, Point, Rotation};
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 200;
+// Largest per-channel difference at which two pixels are still considered equal.
+const CHANNEL_TOLERANCE: u8 = 4;
+// Share of pixels allowed to exceed the channel tolerance, to absorb float rounding differences.
+const MAX_MISMATCH_RATIO: f32 = 0.001;
+
+struct Pose {
+    name: &'static str,
+    pos: Point,
+    rotation: f32,
+    fov: u32,
+    columns_per_ray: u32,
+    fish_eye_correction: bool,
+    eye_height: f32,
+    pitch: f32,
+}
+
+fn poses() -> Vec<Pose> {
+    let spawn = Point::new(6.5, 7.5);
+    vec![
+        Pose {
+            name: "spawn_east",
+            pos: spawn,
+            rotation: 0.0,
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: false,
+            eye_height: 0.5,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "spawn_south_east",
+            pos: spawn,
+            rotation: 45.0,
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: false,
+            eye_height: 0.5,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "spawn_south",
+            pos: spawn,
+            rotation: 90.0,
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: false,
+            eye_height: 0.5,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "spawn_west_fish_eye_correction",
+            pos: spawn,
+            rotation: 180.0,
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: true,
+            eye_height: 0.5,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "spawn_north_narrow_fov",
+            pos: spawn,
+            rotation: 270.0,
+            fov: 60,
+            columns_per_ray: 1,
+            fish_eye_correction: false,
+            eye_height: 0.5,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "corner_low_resolution",
+            pos: Point::new(1.5, 8.5),
+            rotation: 30.0,
+            fov: 90,
+            columns_per_ray: 8,
+            fish_eye_correction: false,
+            eye_height: 0.5,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "through_grate",
+            pos: Point::new(5.5, 4.6),
+            rotation: 297.0,
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: true,
+            eye_height: 0.5,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "close_to_wall",
+            pos: Point::new(5.2, 2.5),
+            rotation: 0.0,
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: true,
+            eye_height: 0.5,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "over_low_walls",
+            pos: Point::new(6.5, 6.5),
+            rotation: 110.0,
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: true,
+            eye_height: 0.9,
+            pitch: 0.0,
+        },
+        Pose {
+            name: "looking_up_at_beam",
+            pos: spawn,
+            rotation: 80.0,
+            fov: 90,
+            columns_per_ray: 1,
+            fish_eye_correction: true,
+            eye_height: 0.5,
+            pitch: 20.0,
+        },
+    ]
+}
+
+fn render(pose: &Pose, level: &Level) -> RgbaImage {
+    let mut camera = Camera::new(pose.pos);
+    camera.rotation = Rotation::new(pose.rotation);
+    camera.fov = pose.fov;
+    camera.columns_per_ray = pose.columns_per_ray;
+    camera.fish_eye_correction = pose.fish_eye_correction;
+    camera.eye_height = pose.eye_height;
+    camera.pitch = pose.pitch;
+
+    let mut frame_buffer = FrameBuffer::new(WIDTH, HEIGHT);
+    frame_buffer.draw_floor(&camera, level);
+    frame_buffer.draw_walls(&camera, level);
+    frame_buffer.draw_sprites(&camera, level);
+
+    RgbaImage::from_raw(WIDTH as u32, HEIGHT as u32, frame_buffer.buffer().to_vec()).unwrap()
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn diff_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden_diff")
+}
+
+// Returns the number of mismatching pixels and an image with them marked in red over a dimmed copy
+// of the expected output.
+fn compare(expected: &RgbaImage, actual: &RgbaImage) -> (usize, RgbaImage) {
+    let mut diff = RgbaImage::new(expected.width(), expected.height());
+    let mut mismatches: usize = 0;
+
+    for (x, y, expected_pixel) in expected.enumerate_pixels() {
+        let actual_pixel = actual.get_pixel(x, y);
+        let matches = expected_pixel
+            .0
+            .iter()
+            .zip(actual_pixel.0.iter())
+            .all(|(a, b)| a.abs_diff(*b) <= CHANNEL_TOLERANCE);
+
+        if matches {
+            let [r, g, b, _] = expected_pixel.0;
+            diff.put_pixel(x, y, Rgba([r / 4, g / 4, b / 4, 255]));
+        } else {
+            mismatches += 1;
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        }
+    }
+
+    (mismatches, diff)
+}
+
+#[test]
+fn renderer_matches_golden_images() {
+    let level = Level::from_toml(include_str!("golden/level.toml")).unwrap();
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures: Vec<String> = vec![];
+
+    for pose in poses() {
+        let actual = render(&pose, &level);
+        let golden_path = golden_dir().join(format!("{}.png", pose.name));
+
+        if update {
+            actual.save(&golden_path).unwrap();
+            continue;
+        }
+
+        let expected = match image::open(&golden_path) {
+            Ok(expected) => expected.to_rgba8(),
+            Err(e) => {
+                failures.push(format!(
+                    "{}: couldn't open {} ({}), run with UPDATE_GOLDEN=1 to create it",
+                    pose.name,
+                    golden_path.display(),
+                    e
+                ));
+                continue;
+            }
+        };
+        if expected.dimensions() != actual.dimensions() {
+            failures.push(format!(
+                "{}: expected size {:?}, got {:?}",
+                pose.name,
+                expected.dimensions(),
+                actual.dimensions()
+            ));
+            continue;
+        }
+
+        let (mismatches, diff) = compare(&expected, &actual);
+        if (mismatches as f32) > (WIDTH * HEIGHT) as f32 * MAX_MISMATCH_RATIO {
+            std::fs::create_dir_all(diff_dir()).unwrap();
+            let diff_path = diff_dir().join(format!("{}_diff.png", pose.name));
+            let actual_path = diff_dir().join(format!("{}_actual.png", pose.name));
+            diff.save(&diff_path).unwrap();
+            actual.save(&actual_path).unwrap();
+            failures.push(format!(
+                "{}: {} pixels differ, see {} and {}",
+                pose.name,
+                mismatches,
+                diff_path.display(),
+                actual_path.display()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "golden image mismatches:\n{}", failures.join("\n"));
+}
@@ -0,0 +1,133 @@
+use raycaster::{Action, GamepadSettings, InputState, KeyBindings, KeyBindingsError};
+
+#[test]
+fn holds_several_keys_at_once() {
+    let mut input = InputState::default();
+    assert_eq!(input.key_down("KeyW"), Some(Action::MoveForward));
+    input.key_down("KeyD");
+    let info = input.input_info();
+    assert!(info.forward && info.right && !info.left);
+
+    input.key_up("KeyW");
+    let info = input.input_info();
+    assert!(!info.forward && info.right);
+}
+
+#[test]
+fn opposite_keys_cancel_out() {
+    let mut input = InputState::default();
+    input.key_down("KeyW");
+    input.key_down("KeyS");
+    let info = input.input_info();
+    assert!(!info.forward && !info.backward);
+}
+
+#[test]
+fn right_mouse_button_walks_forward_until_released() {
+    let mut input = InputState::default();
+    input.mouse_down(2);
+    assert!(input.is_mouse_button_held(2) && input.input_info().forward);
+
+    input.key_down("KeyD");
+    input.release_all();
+    assert!(!input.is_held(Action::StrafeRight) && !input.input_info().forward);
+}
+
+#[test]
+fn rebinds_listed_actions_only() {
+    let bindings = KeyBindings::from_toml("move_forward = [\"KeyZ\", \"ArrowUp\"]\nturn_left = [\"KeyA\"]\n").unwrap();
+    assert_eq!(bindings.action("KeyZ"), Some(Action::MoveForward));
+    assert_eq!(bindings.action("ArrowUp"), Some(Action::MoveForward));
+    assert_eq!(bindings.action("KeyW"), None);
+    // A key bound to a new action stops triggering its old one
+    assert_eq!(bindings.action("KeyA"), Some(Action::TurnLeft));
+    assert_eq!(bindings.action("KeyS"), Some(Action::MoveBackward));
+
+    let mut input = InputState::new(bindings);
+    input.key_down("ArrowUp");
+    assert!(input.input_info().forward);
+}
+
+#[test]
+fn bundled_file_matches_the_defaults() {
+    let bindings = KeyBindings::from_toml(include_str!("../key_bindings.toml")).unwrap();
+    assert_eq!(bindings.to_toml(), KeyBindings::default().to_toml());
+    assert_eq!(
+        KeyBindings::from_toml(&bindings.to_toml()).unwrap().to_toml(),
+        bindings.to_toml()
+    );
+}
+
+#[test]
+fn reports_unknown_actions() {
+    let error = KeyBindings::from_toml("fly = [\"KeyF\"]").unwrap_err();
+    assert!(error.to_string().contains("fly"));
+}
+
+#[test]
+fn gamepad_sticks_move_smoothly_past_the_dead_zone() {
+    let settings = GamepadSettings::default();
+    assert_eq!(settings.apply_dead_zone(0.1), 0.0);
+    assert_eq!(settings.apply_dead_zone(-1.0), -1.0);
+    assert!((settings.apply_dead_zone(0.575) - 0.5).abs() < 0.001);
+
+    let mut input = InputState::default();
+    // Left stick pushed up and a little left, right stick resting slightly off centre
+    input.update_gamepad(&[-0.05, -1.0, 0.1, 0.0], &[]);
+    let info = input.input_info();
+    assert_eq!(
+        (info.move_amount, info.strafe_amount, info.turn_amount),
+        (1.0, 0.0, 0.0)
+    );
+    assert!(!info.forward);
+}
+
+#[test]
+fn gamepad_buttons_trigger_once_per_press() {
+    let mut input = InputState::default();
+    assert_eq!(
+        input.update_gamepad(&[], &[true, false, true]),
+        vec![Action::Jump, Action::UseDoor]
+    );
+    assert_eq!(input.update_gamepad(&[], &[true, false, true]), vec![]);
+    assert_eq!(
+        input.update_gamepad(&[], &[false, true, true]),
+        vec![Action::ToggleCrouch]
+    );
+}
+
+#[test]
+fn loads_gamepad_settings() {
+    let bindings = KeyBindings::from_toml("jump = [\"KeyJ\"]\n\n[gamepad]\ndead_zone = 0.3\n").unwrap();
+    assert_eq!(bindings.gamepad.dead_zone, 0.3);
+    assert_eq!(bindings.gamepad.turn_sensitivity, 1.0);
+    assert_eq!(bindings.action("KeyJ"), Some(Action::Jump));
+}
+
+#[test]
+fn rejects_invalid_gamepad_settings() {
+    for settings in [
+        "dead_zone = 1.0",
+        "dead_zone = -0.1",
+        "dead_zone = nan",
+        "move_sensitivity = -1.0",
+        "turn_sensitivity = nan",
+    ] {
+        let error = KeyBindings::from_toml(&format!("[gamepad]\n{}\n", settings)).unwrap_err();
+        assert!(
+            matches!(error, KeyBindingsError::InvalidGamepadSettings(_)),
+            "{}",
+            settings
+        );
+    }
+}
+
+#[test]
+fn resting_stick_is_zero_with_any_dead_zone() {
+    let settings = GamepadSettings {
+        dead_zone: 0.0,
+        ..GamepadSettings::default()
+    };
+    assert_eq!(settings.apply_dead_zone(0.0), 0.0);
+    assert_eq!(settings.apply_dead_zone(0.5), 0.5);
+}
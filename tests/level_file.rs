@@ -0,0 +1,172 @@
+use raycaster::{Color, Face, Level, LevelErrorKind, Point, Texture, TextureRegistry};
+
+const TILES: &str = r#"
+[spawn]
+x = 1.5
+y = 1.5
+
+[[tiles]]
+texture = "blank"
+solid = false
+transparent = true
+
+[[tiles]]
+texture = "brick_wall"
+solid = true
+transparent = false
+"#;
+
+fn level_with_grid(grid: &str) -> String {
+    format!("grid = {}\n{}", grid, TILES)
+}
+
+#[test]
+fn loads_valid_level() {
+    let level = Level::from_toml(&level_with_grid("[\n    [1, 1, 1],\n    [1, 0, 1],\n]")).unwrap();
+    assert_eq!((level.width(), level.height()), (3, 2));
+    assert_eq!((level.spawn.pos.x, level.spawn.pos.y), (1.5, 1.5));
+}
+
+#[test]
+fn loads_entities() {
+    let source = format!(
+        "{}\n[[entities]]\nx = 1.5\ny = 1.5\ntexture = \"barrel\"\n\n[[entities]]\nx = 0.5\ny = 1.0\ntexture = \"grate\"\nscale = 0.5\n",
+        level_with_grid("[\n    [1, 1, 1],\n    [1, 0, 1],\n]")
+    );
+    let level = Level::from_toml(&source).unwrap();
+    assert_eq!(level.entities.len(), 2);
+    assert_eq!((level.entities[0].pos.x, level.entities[0].scale), (1.5, 1.0));
+    assert_eq!(level.entities[1].scale, 0.5);
+    assert!(level.get_entity_texture(&level.entities[1]).has_alpha);
+}
+
+#[test]
+fn animated_textures_follow_level_time() {
+    let source = format!(
+        "{}\n[animations.flicker]\nframes = [\"brick_wall\", \"grate\"]\ndurations = [0.5, 0.25]\n",
+        level_with_grid("[\n    [1, 1, 1],\n    [1, 0, 1],\n]")
+            .replace("texture = \"brick_wall\"", "texture = \"flicker\"")
+    );
+    let mut level = Level::from_toml(&source).unwrap();
+    let wall = Point::new(0.5, 0.5);
+    assert!(!level.get_texture(&wall).has_alpha);
+    level.update(0.6);
+    assert!(level.get_texture(&wall).has_alpha);
+    level.update(0.2);
+    assert!(!level.get_texture(&wall).has_alpha);
+}
+
+#[test]
+fn loads_sky() {
+    let grid = level_with_grid("[\n    [1, 1, 1],\n    [1, 0, 1],\n]");
+    assert!(Level::from_toml(&grid).unwrap().get_sky_texture().is_none());
+
+    let level = Level::from_toml(&format!("sky = \"sky\"\n{}", grid)).unwrap();
+    assert_eq!(level.get_sky_texture().unwrap().width, 1024);
+
+    let error = Level::from_toml(&format!("sky = \"stars\"\n{}", grid)).unwrap_err();
+    assert!(matches!(error.kind, LevelErrorKind::UnknownTexture(_)));
+}
+
+#[test]
+fn reports_invalid_animation() {
+    let source = format!(
+        "{}\n[animations.flicker]\nframes = [\"brick_wall\", \"grate\"]\ndurations = [0.5, 0.25, 1.0]\n",
+        level_with_grid("[\n    [1, 1, 1],\n    [1, 0, 1],\n]")
+    );
+    let error = Level::from_toml(&source).unwrap_err();
+    assert!(matches!(error.kind, LevelErrorKind::InvalidAnimation { ref name, .. } if name == "flicker"));
+}
+
+#[test]
+fn reports_ragged_row() {
+    let error = Level::from_toml(&level_with_grid("[\n    [1, 1, 1],\n    [1, 0],\n]")).unwrap_err();
+    assert_eq!(error.kind, LevelErrorKind::RaggedRow { expected: 3, found: 2 });
+    assert_eq!((error.line, error.column), (3, 5));
+}
+
+#[test]
+fn reports_unknown_tile_index() {
+    let error = Level::from_toml(&level_with_grid("[\n    [1, 1, 1],\n    [1, 2, 1],\n]")).unwrap_err();
+    assert_eq!(error.kind, LevelErrorKind::UnknownTileIndex { index: 2, tile_count: 2 });
+    assert_eq!((error.line, error.column), (3, 9));
+}
+
+#[test]
+fn reports_unknown_texture() {
+    let source = level_with_grid("[[1]]").replace("\"brick_wall\"", "\"bricks\"");
+    let error = Level::from_toml(&source).unwrap_err();
+    assert_eq!(error.kind, LevelErrorKind::UnknownTexture("bricks".to_string()));
+    assert_eq!(error.line, 13);
+}
+
+#[test]
+fn reports_malformed_grid() {
+    let error = Level::from_toml(&level_with_grid("[\n    [1, 1, 1],\n    [1, 0, 1\n]")).unwrap_err();
+    assert!(matches!(error.kind, LevelErrorKind::Syntax(_)));
+    assert!(error.line >= 3);
+}
+
+#[test]
+fn reports_directional_entity_with_unsplittable_texture() {
+    let mut textures = TextureRegistry::builtin();
+    textures.insert("odd", Texture::new(12, 1, vec![Color::new(0, 0, 0); 12]));
+    let grid = level_with_grid("[\n    [1, 1, 1],\n    [1, 0, 1],\n]");
+    for texture in ["blank", "odd"] {
+        let source = format!(
+            "{}\n[[entities]]\nx = 1.5\ny = 1.5\ntexture = \"{}\"\nfacing = 0.0\n",
+            grid, texture
+        );
+        let error = Level::from_toml_with_textures(&source, &textures).unwrap_err();
+        assert!(matches!(
+            &error.kind,
+            LevelErrorKind::InvalidDirectionalTexture { texture: name, .. } if name == texture
+        ));
+        // Pointing at the entity's table
+        assert_eq!(
+            error.line,
+            source.lines().position(|line| line == "[[entities]]").unwrap() + 1
+        );
+    }
+
+    // Every frame of an animated directional texture is checked
+    let source = format!(
+        "{}\n[animations.walk]\nframes = [\"guard\", \"odd\"]\ndurations = [0.5]\n\n[[entities]]\nx = 1.5\ny = 1.5\ntexture = \"walk\"\nfacing = 0.0\n",
+        grid
+    );
+    assert!(matches!(
+        Level::from_toml_with_textures(&source, &textures).unwrap_err().kind,
+        LevelErrorKind::InvalidDirectionalTexture { .. }
+    ));
+    assert!(Level::from_toml_with_textures(&source.replace("\"odd\"", "\"guard\""), &textures).is_ok());
+}
+
+#[test]
+fn loads_face_textures() {
+    let source = level_with_grid("[\n    [1, 1, 1],\n    [1, 0, 1],\n]").replace(
+        "texture = \"brick_wall\"\n",
+        "texture = \"brick_wall\"\nnorth = \"grate\"\neast = \"barrel\"\n",
+    );
+    let level = Level::from_toml(&source).unwrap();
+    let registry = TextureRegistry::builtin();
+    let wall = Point::new(0.5, 0.5);
+    for (face, name) in [
+        (Face::North, "grate"),
+        (Face::East, "barrel"),
+        (Face::South, "brick_wall"),
+        (Face::West, "brick_wall"),
+    ] {
+        assert_eq!(
+            level.get_face_texture(&wall, face).layout,
+            registry.get(name).unwrap().layout,
+            "{:?}",
+            face
+        );
+    }
+
+    let source = source.replace("east = \"barrel\"", "west = \"bricks\"");
+    let error = Level::from_toml(&source).unwrap_err();
+    assert_eq!(error.kind, LevelErrorKind::UnknownTexture("bricks".to_string()));
+    let line = source.lines().position(|line| line == "west = \"bricks\"").unwrap() + 1;
+    assert_eq!((error.line, error.column), (line, 8));
+}
@@ -0,0 +1,114 @@
+use raycaster::{Atmosphere, Color, Face, FaceShading, FogFalloff, Level, Light, LightLevel, Point, Rotation};
+
+// Two rooms split by a wall, with a red light in the left one
+const LEVEL: &str = r#"
+grid = [
+    [1, 1, 1, 1, 1, 1, 1],
+    [1, 0, 0, 1, 0, 0, 1],
+    [1, 0, 0, 1, 0, 0, 1],
+    [1, 1, 1, 1, 1, 1, 1],
+]
+
+[spawn]
+x = 1.5
+y = 1.5
+
+[[lights]]
+x = 1.5
+y = 1.5
+color = [255, 0, 0]
+radius = 4.0
+
+[[tiles]]
+texture = "blank"
+solid = false
+transparent = true
+
+[[tiles]]
+texture = "brick_wall"
+solid = true
+transparent = false
+"#;
+
+#[test]
+fn walls_cast_shadows_from_baked_lights() {
+    let level = Level::from_toml(LEVEL).unwrap();
+
+    let lit = level.get_light(&Point::new(2.0, 1.5));
+    assert!(lit.r > 0.5 && lit.g == 0.0 && lit.b == 0.0);
+    assert_eq!(level.get_light(&Point::new(4.5, 1.5)), LightLevel::default());
+
+    // The dividing wall is lit on the side facing the light only
+    assert!(level.get_face_light((3, 1), Face::West, &Point::new(3.0, 1.5)).r > 0.0);
+    assert_eq!(
+        level.get_face_light((3, 1), Face::East, &Point::new(4.0, 1.5)),
+        LightLevel::default()
+    );
+}
+
+#[test]
+fn dynamic_lights_add_to_the_lightmap() {
+    let mut level = Level::from_toml(LEVEL).unwrap();
+    level
+        .dynamic_lights
+        .push(Light::new(Point::new(4.5, 1.5), Color::new(0, 0, 255), 2.0));
+
+    let light = level.get_light(&Point::new(4.5, 1.5));
+    assert_eq!((light.r, light.b), (0.0, 1.0));
+    assert!(level.get_light(&Point::new(1.5, 1.5)).b == 0.0);
+}
+
+#[test]
+fn fog_fades_to_its_color_down_to_the_ambient_level() {
+    let atmosphere = Atmosphere {
+        fog_color: Color::new(0, 0, 200),
+        fog_start: 2.0,
+        fog_end: 6.0,
+        falloff: FogFalloff::Linear,
+        ambient: 0.25,
+    };
+    assert_eq!(atmosphere.brightness(1.0), 1.0);
+    assert_eq!(atmosphere.brightness(4.0), 0.5);
+    assert_eq!(atmosphere.brightness(10.0), 0.25);
+
+    let white = Color::new(200, 200, 200);
+    assert_eq!(
+        atmosphere.shade(&white, 4.0, LightLevel::default()),
+        Color::new(100, 100, 200)
+    );
+    assert_eq!(
+        atmosphere.shade(&white, 4.0, LightLevel::new(0.5, 0.0, 0.0)),
+        Color::new(200, 100, 200)
+    );
+}
+
+#[test]
+fn loads_atmosphere() {
+    let level = Level::from_toml(&format!(
+        "{}\n[atmosphere]\nfog_color = [10, 20, 30]\nfalloff = \"exponential\"\nfog_end = 8.0\n",
+        LEVEL
+    ))
+    .unwrap();
+    assert_eq!(level.atmosphere.fog_color, Color::new(10, 20, 30));
+    assert_eq!(level.atmosphere.falloff, FogFalloff::Exponential);
+    assert_eq!(level.atmosphere.fog_start, Atmosphere::default().fog_start);
+    assert!(level.atmosphere.brightness(8.0) < 0.1);
+
+    assert_eq!(
+        Level::from_toml(LEVEL).unwrap().atmosphere.falloff,
+        FogFalloff::InverseDistance
+    );
+}
+
+#[test]
+fn sun_lights_the_faces_turned_towards_it() {
+    let shading = FaceShading::from_sun(&Rotation::new(0.0), 0.4);
+    assert_eq!(shading.brightness(Face::East), 1.0);
+    assert_eq!(shading.brightness(Face::West), 0.4);
+    assert!((shading.brightness(Face::North) - 0.4).abs() < 0.001);
+
+    let level = Level::from_toml(&format!("{}\n[face_shading]\nnorth = 0.5\n", LEVEL)).unwrap();
+    assert_eq!(level.face_shading.brightness(Face::North), 0.5);
+    assert_eq!(level.face_shading.brightness(Face::South), 1.0);
+    assert_eq!(Level::from_toml(LEVEL).unwrap().face_shading, FaceShading::default());
+}
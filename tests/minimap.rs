@@ -0,0 +1,62 @@
+use raycaster::{Camera, FrameBuffer, Level, Point};
+
+const TILE_SIZE: usize = 16;
+const WIDTH: usize = 320;
+const HEIGHT: usize = 200;
+
+// A floor with a single wall in the middle and nothing around it, so rays run off the grid
+const OPEN: &str = r#"
+grid = [
+    [0, 0, 0],
+    [0, 1, 0],
+    [0, 0, 0],
+]
+
+[spawn]
+x = 0.5
+y = 0.5
+
+[[tiles]]
+texture = "blank"
+solid = false
+transparent = true
+
+[[tiles]]
+texture = "brick_wall"
+solid = true
+transparent = false
+"#;
+
+#[test]
+fn rays_leaving_the_level_stay_on_the_minimap() {
+    let level = Level::from_toml(OPEN).unwrap();
+    // The left and bottom edges of the minimap's tiles
+    let (left, bottom) = (
+        WIDTH - (level.width() + 1) * TILE_SIZE,
+        (level.height() + 1) * TILE_SIZE,
+    );
+
+    for pos in [
+        Point::new(0.5, 0.5),
+        Point::new(2.5, 1.5),
+        Point::new(50.0, 7.5),
+        Point::new(-5.0, -5.0),
+    ] {
+        let mut frame_buffer = FrameBuffer::new(WIDTH, HEIGHT);
+        frame_buffer.draw_minimap(&Camera::new(pos), &level);
+
+        let green: Vec<(usize, usize)> = frame_buffer
+            .buffer()
+            .chunks(4)
+            .enumerate()
+            .filter(|(_, pixel)| pixel[..3] == [0, 255, 0])
+            .map(|(i, _)| (i % WIDTH, i / WIDTH))
+            .collect();
+        assert!(!green.is_empty(), "no rays drawn from {:?}", (pos.x, pos.y));
+        assert!(
+            green.iter().all(|&(x, y)| x >= left && y <= bottom),
+            "rays drawn off the minimap from {:?}",
+            (pos.x, pos.y)
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use raycaster::{cast_ray, cast_ray_layers, Face, Level, Point, Rotation};
+
+const LEVEL: &str = r#"
+grid = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 2, 3, 1],
+    [1, 1, 1, 1, 1],
+]
+
+[spawn]
+x = 1.5
+y = 1.5
+
+[[tiles]]
+texture = "blank"
+solid = false
+transparent = true
+
+[[tiles]]
+texture = "brick_wall"
+solid = true
+transparent = false
+height = 2.0
+
+[[tiles]]
+texture = "brick_wall"
+solid = true
+transparent = false
+height = 0.5
+
+[[tiles]]
+texture = "brick_wall"
+solid = true
+transparent = false
+elevation = 0.5
+height = 1.5
+"#;
+
+#[test]
+fn rays_continue_past_walls_that_leave_gaps() {
+    let level = Level::from_toml(LEVEL).unwrap();
+    assert_eq!(level.max_wall_top(), 2.0);
+
+    let hits = cast_ray_layers(&Point::new(1.5, 1.5), &Rotation::new(0.0), &level, 16);
+    let tiles: Vec<(i32, i32)> = hits.iter().map(|hit| hit.tile).collect();
+    assert_eq!(tiles, vec![(2, 1), (3, 1), (4, 1)]);
+    assert!((hits[0].distance - 0.5).abs() < 0.001);
+    assert!((hits[0].exit_distance - 1.5).abs() < 0.001);
+}
+
+const PILLAR: &str = r#"
+grid = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 1, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+]
+
+[spawn]
+x = 1.5
+y = 1.5
+
+[[tiles]]
+texture = "blank"
+solid = false
+transparent = true
+
+[[tiles]]
+texture = "brick_wall"
+solid = true
+transparent = false
+"#;
+
+#[test]
+fn pillar_faces_read_left_to_right_from_every_side() {
+    let level = Level::from_toml(PILLAR).unwrap();
+    for (pos, rotation, face) in [
+        (Point::new(2.5, 1.3), 90.0, Face::North),
+        (Point::new(2.5, 3.7), 270.0, Face::South),
+        (Point::new(3.7, 2.5), 180.0, Face::East),
+        (Point::new(1.3, 2.5), 0.0, Face::West),
+    ] {
+        let left = cast_ray(&pos, &Rotation::new(rotation - 5.0), &level);
+        let right = cast_ray(&pos, &Rotation::new(rotation + 5.0), &level);
+        for hit in [&left, &right] {
+            assert_eq!((hit.tile, hit.face), ((2, 2), face));
+        }
+        assert!(
+            left.wall_offset < right.wall_offset,
+            "{:?}: {} should be left of {}",
+            face,
+            left.wall_offset,
+            right.wall_offset
+        );
+    }
+}
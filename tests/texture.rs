@@ -0,0 +1,104 @@
+use raycaster::{Animation, Color, Texture, TextureError, TextureRegistry};
+
+// An atlas with a single texture named "t", laid out like texture_parser writes it
+fn atlas(width: u32, height: u32, mip_levels: u32, pixels: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = b"RTEX".to_vec();
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(b"t");
+    bytes.extend_from_slice(&width.to_le_bytes());
+    bytes.extend_from_slice(&height.to_le_bytes());
+    bytes.extend_from_slice(&mip_levels.to_le_bytes());
+    bytes.extend_from_slice(pixels);
+    bytes
+}
+
+fn load(bytes: &[u8]) -> Result<(), TextureError> {
+    TextureRegistry::new().load_atlas(bytes)
+}
+
+#[test]
+fn loads_texture_and_skips_its_mip_levels() {
+    // 2x2 then 1x1
+    let pixels: Vec<u8> = (0..20).collect();
+    let mut registry = TextureRegistry::new();
+    registry.load_atlas(&atlas(2, 2, 2, &pixels)).unwrap();
+    let texture = registry.get("t").unwrap();
+    assert_eq!((texture.width, texture.height, texture.layout.len()), (2, 2, 4));
+    assert_eq!(texture.layout[3].r, 12);
+}
+
+#[test]
+fn rejects_garbage_and_truncated_atlases() {
+    for bytes in [
+        b"".to_vec(),
+        b"not an atlas".to_vec(),
+        atlas(2, 2, 1, &[0; 15]),
+        atlas(2, 2, 2, &[0; 16]),
+    ] {
+        assert!(matches!(load(&bytes), Err(TextureError::InvalidAtlas(_))));
+    }
+}
+
+#[test]
+fn rejects_impossible_sizes_without_panicking() {
+    for bytes in [
+        atlas(0, 0, 1, &[]),
+        atlas(0, 4, 1, &[]),
+        atlas(1, 1, 0, &[]),
+        atlas(1, 1, 70, &[0; 4]),
+        atlas(u32::MAX, u32::MAX, 1, &[0; 4]),
+        atlas(u32::MAX, 1, 1, &[0; 4]),
+    ] {
+        assert!(matches!(load(&bytes), Err(TextureError::InvalidAtlas(_))));
+    }
+}
+
+#[test]
+fn animation_needs_a_positive_duration_for_every_frame() {
+    for (frames, durations) in [
+        (vec![], vec![]),
+        (vec![1, 2], vec![0.5]),
+        (vec![1], vec![0.5, 0.5]),
+        (vec![1, 2], vec![0.5, 0.0]),
+        (vec![1], vec![f32::NAN]),
+    ] {
+        assert!(matches!(
+            Animation::new(frames, durations),
+            Err(TextureError::InvalidAnimation(_))
+        ));
+    }
+
+    let animation = Animation::new(vec![1, 2], vec![0.5, 0.25]).unwrap();
+    assert_eq!(animation.frames(), &[1, 2]);
+    assert_eq!(
+        (
+            animation.frame_at(0.4),
+            animation.frame_at(0.6),
+            animation.frame_at(0.8)
+        ),
+        (1, 2, 1)
+    );
+}
+
+#[test]
+fn splits_frames_side_by_side() {
+    let texture = Texture::new(4, 1, (0..4).map(|i| Color::new(i, 0, 0)).collect());
+    let frames = texture.split_frames(2);
+    assert_eq!(frames.len(), 2);
+    assert_eq!((frames[1].width, frames[1].height), (2, 1));
+    assert_eq!(frames[1].layout, vec![Color::new(2, 0, 0), Color::new(3, 0, 0)]);
+}
+
+#[test]
+#[should_panic(expected = "can't be split into 0 frames")]
+fn splitting_into_no_frames_panics() {
+    Texture::blank().split_frames(0);
+}
+
+#[test]
+#[should_panic(expected = "can't be split into 3 frames")]
+fn splitting_off_columns_panics() {
+    Texture::new(4, 1, vec![Color::new(0, 0, 0); 4]).split_frames(3);
+}
@@ -1,13 +1,341 @@
-use std::fs::File;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process;
 
-fn main() -> std::io::Result<()> {
-    let im = image::open(&Path::new("textures/floor_wood.jpeg")).unwrap();
-    // println!("{:?}", im.as_bytes().len());
+use image::imageops::FilterType;
+use image::{ImageFormat, RgbaImage};
 
-    let mut file = File::create("out.txt")?;
-    file.write_all(format!("length: {:?}", im.as_bytes().len()).as_bytes())?;
-    file.write_all(format!("vec!{:?};", im.as_bytes()).as_bytes())?;
-    Ok(())
+const USAGE: &str = "Usage: texture_parser [options] <input_dir> <output_dir>
+
+Converts every PNG / JPEG image in <input_dir> to RGBA and writes them, together with a
+manifest.toml listing their names and sizes, to <output_dir>.
+
+Options:
+    --format <atlas|rust>   Output format (default: atlas)
+                                atlas: textures.atlas, loadable with TextureRegistry::load_atlas
+                                rust:  texture_consts.rs with one RGBA const per texture
+    --max-size <u32>        Largest allowed width / height (default: 1024)
+    --resize <u32>          Resize every texture to <size>x<size> first
+    --mipmaps               Also emit every mip level down to 1x1";
+
+// Atlas layout, all integers little endian:
+//     b"RTEX", version: u32, texture count: u32
+//     for every texture: name length: u16, name: utf8, width: u32, height: u32, mip level count: u32,
+//     then the RGBA pixels of every mip level, each half the size of the previous one
+const ATLAS_MAGIC: &[u8; 4] = b"RTEX";
+const ATLAS_VERSION: u32 = 1;
+
+#[derive(PartialEq)]
+enum Format {
+    Atlas,
+    Rust,
+}
+
+struct Options {
+    input_dir: PathBuf,
+    output_dir: PathBuf,
+    format: Format,
+    max_size: u32,
+    resize: Option<u32>,
+    mipmaps: bool,
+}
+
+struct ParsedTexture {
+    name: String,
+    source: String,
+    mip_levels: Vec<RgbaImage>,
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {}", flag))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {}: {:?}", flag, value))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut format = Format::Atlas;
+    let mut max_size: u32 = 1024;
+    let mut resize: Option<u32> = None;
+    let mut mipmaps = false;
+    let mut paths: Vec<PathBuf> = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match parse_value::<String>(&arg, args.next())?.as_str() {
+                    "atlas" => Format::Atlas,
+                    "rust" => Format::Rust,
+                    other => return Err(format!("Unknown format: {:?}", other)),
+                }
+            }
+            "--max-size" => max_size = parse_value(&arg, args.next())?,
+            "--resize" => resize = Some(parse_value(&arg, args.next())?),
+            "--mipmaps" => mipmaps = true,
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg)),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    if paths.len() != 2 {
+        return Err("Expected an input and an output directory".to_string());
+    }
+    if let Some(size) = resize {
+        if !size.is_power_of_two() || size > max_size {
+            return Err(format!(
+                "--resize must be a power of two no larger than {}, got {}",
+                max_size, size
+            ));
+        }
+    }
+
+    let output_dir = paths.pop().unwrap();
+    let input_dir = paths.pop().unwrap();
+    Ok(Options {
+        input_dir,
+        output_dir,
+        format,
+        max_size,
+        resize,
+        mipmaps,
+    })
+}
+
+// Turns a file stem like "Floor Wood-2" into "floor_wood_2".
+fn texture_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn parse_texture(path: &Path, options: &Options) -> Result<ParsedTexture, String> {
+    let mut image = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
+
+    if let Some(size) = options.resize {
+        image = image::imageops::resize(&image, size, size, FilterType::Lanczos3);
+    }
+    let (width, height) = image.dimensions();
+    if !width.is_power_of_two() || !height.is_power_of_two() {
+        return Err(format!("{}x{} is not a power of two", width, height));
+    }
+    if width > options.max_size || height > options.max_size {
+        return Err(format!(
+            "{}x{} is larger than the maximum of {}x{}",
+            width, height, options.max_size, options.max_size
+        ));
+    }
+
+    let mut mip_levels = vec![image];
+    if options.mipmaps {
+        while mip_levels.last().unwrap().dimensions() != (1, 1) {
+            let (width, height) = mip_levels.last().unwrap().dimensions();
+            let next = image::imageops::resize(
+                mip_levels.last().unwrap(),
+                (width / 2).max(1),
+                (height / 2).max(1),
+                FilterType::Triangle,
+            );
+            mip_levels.push(next);
+        }
+    }
+
+    let name = texture_name(path);
+    // Rust output turns names into consts, which can't start with a digit
+    if options.format == Format::Rust && !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "texture name {:?} has to start with a letter to be used as a Rust const",
+            name
+        ));
+    }
+
+    Ok(ParsedTexture {
+        name,
+        source: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        mip_levels,
+    })
+}
+
+fn parse_textures(options: &Options) -> Result<Vec<ParsedTexture>, Vec<String>> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(&options.input_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && matches!(
+                        ImageFormat::from_path(path),
+                        Ok(ImageFormat::Png) | Ok(ImageFormat::Jpeg)
+                    )
+            })
+            .collect(),
+        Err(e) => return Err(vec![format!("{}: {}", options.input_dir.display(), e)]),
+    };
+    paths.sort();
+
+    let mut textures: Vec<ParsedTexture> = vec![];
+    let mut errors: Vec<String> = vec![];
+    for path in paths {
+        match parse_texture(&path, options) {
+            Ok(texture) => {
+                if let Some(other) = textures.iter().find(|other| other.name == texture.name) {
+                    errors.push(format!(
+                        "{}: texture name {:?} is already used by {}",
+                        path.display(),
+                        texture.name,
+                        other.source
+                    ));
+                } else {
+                    textures.push(texture);
+                }
+            }
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    if textures.is_empty() && errors.is_empty() {
+        errors.push(format!("{}: no PNG or JPEG images found", options.input_dir.display()));
+    }
+    if errors.is_empty() {
+        Ok(textures)
+    } else {
+        Err(errors)
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+fn write_atlas(textures: &[ParsedTexture], path: &Path) -> std::io::Result<()> {
+    let mut bytes: Vec<u8> = vec![];
+    bytes.extend_from_slice(ATLAS_MAGIC);
+    bytes.extend_from_slice(&ATLAS_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(textures.len() as u32).to_le_bytes());
+
+    for texture in textures {
+        let (width, height) = texture.mip_levels[0].dimensions();
+        let name_length = u16::try_from(texture.name.len()).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("texture name {:?} is longer than {} bytes", texture.name, u16::MAX),
+            )
+        })?;
+        bytes.extend_from_slice(&name_length.to_le_bytes());
+        bytes.extend_from_slice(texture.name.as_bytes());
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&(texture.mip_levels.len() as u32).to_le_bytes());
+        for level in &texture.mip_levels {
+            bytes.extend_from_slice(level.as_raw());
+        }
+    }
+
+    fs::File::create(path)?.write_all(&bytes)
+}
+
+fn write_rust(textures: &[ParsedTexture], path: &Path) -> std::io::Result<()> {
+    let mut source = String::from("// Generated by texture_parser, don't edit by hand.\n");
+
+    for texture in textures {
+        for (level, image) in texture.mip_levels.iter().enumerate() {
+            let const_name = if level == 0 {
+                texture.name.to_uppercase()
+            } else {
+                format!("{}_MIP_{}", texture.name.to_uppercase(), level)
+            };
+            let _ = writeln!(
+                source,
+                "pub const {}: (usize, usize, [u8; {}]) = ({}, {}, {:?});",
+                const_name,
+                image.as_raw().len(),
+                image.width(),
+                image.height(),
+                image.as_raw()
+            );
+        }
+    }
+
+    fs::File::create(path)?.write_all(source.as_bytes())
+}
+
+fn write_manifest(textures: &[ParsedTexture], options: &Options, path: &Path) -> std::io::Result<()> {
+    let mut manifest = format!(
+        "format = {:?}\n",
+        if options.format == Format::Atlas {
+            "atlas"
+        } else {
+            "rust"
+        }
+    );
+
+    for texture in textures {
+        let (width, height) = texture.mip_levels[0].dimensions();
+        let _ = write!(
+            manifest,
+            "\n[[textures]]\nname = {:?}\nsource = {:?}\nwidth = {}\nheight = {}\nmip_levels = {}\n",
+            texture.name,
+            texture.source,
+            width,
+            height,
+            texture.mip_levels.len()
+        );
+    }
+
+    fs::File::create(path)?.write_all(manifest.as_bytes())
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{}\n", e);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let textures = match parse_textures(&options) {
+        Ok(textures) => textures,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("Error: {}", e);
+            }
+            process::exit(1);
+        }
+    };
+
+    let result = fs::create_dir_all(&options.output_dir)
+        .and_then(|_| match options.format {
+            Format::Atlas => write_atlas(&textures, &options.output_dir.join("textures.atlas")),
+            Format::Rust => write_rust(&textures, &options.output_dir.join("texture_consts.rs")),
+        })
+        .and_then(|_| write_manifest(&textures, &options, &options.output_dir.join("manifest.toml")));
+    if let Err(e) = result {
+        eprintln!("Error writing to {}: {}", options.output_dir.display(), e);
+        process::exit(1);
+    }
+
+    for texture in &textures {
+        let (width, height) = texture.mip_levels[0].dimensions();
+        println!(
+            "{} ({}): {}x{}, {} mip levels",
+            texture.name,
+            texture.source,
+            width,
+            height,
+            texture.mip_levels.len()
+        );
+    }
 }
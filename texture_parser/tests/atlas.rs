@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use image::{Rgba, RgbaImage};
+use raycaster::{Color, TextureRegistry};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Runs texture_parser with `options` on the images in `input`, writing to `output`
+fn run(options: &[&str], input: &Path, output: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_texture_parser"))
+        .args(options)
+        .arg(input)
+        .arg(output)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[test]
+fn written_atlas_loads_with_every_texture() {
+    let input = temp_dir("atlas_input");
+    let output = temp_dir("atlas_output");
+    let checker = RgbaImage::from_fn(4, 2, |x, y| {
+        if (x + y) % 2 == 0 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 255, 128])
+        }
+    });
+    checker.save(input.join("checker.png")).unwrap();
+    RgbaImage::from_pixel(8, 2, Rgba([10, 20, 30, 255]))
+        .save(input.join("plain.png"))
+        .unwrap();
+
+    assert!(run(&["--mipmaps"], &input, &output).status.success());
+
+    let mut registry = TextureRegistry::new();
+    registry
+        .load_atlas(&fs::read(output.join("textures.atlas")).unwrap())
+        .unwrap();
+
+    let texture = registry.get("checker").unwrap();
+    assert_eq!((texture.width, texture.height), (4, 2));
+    assert!(texture.has_alpha);
+    for (i, pixel) in checker.pixels().enumerate() {
+        let [r, g, b, a] = pixel.0;
+        assert_eq!(texture.layout[i], Color::new_with_alpha(r, g, b, a));
+    }
+
+    let texture = registry.get("plain").unwrap();
+    assert_eq!((texture.width, texture.height), (8, 2));
+    assert!(texture.layout.iter().all(|color| *color == Color::new(10, 20, 30)));
+}
+
+#[test]
+fn rejects_sizes_that_arent_powers_of_two_or_too_large() {
+    let input = temp_dir("size_input");
+    let output = temp_dir("size_output");
+    RgbaImage::new(3, 4).save(input.join("odd.png")).unwrap();
+    let result = run(&[], &input, &output);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(stderr(&result).contains("3x4 is not a power of two"));
+
+    let input = temp_dir("size_input");
+    RgbaImage::new(16, 8).save(input.join("large.png")).unwrap();
+    let result = run(&["--max-size", "8"], &input, &output);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(stderr(&result).contains("16x8 is larger than the maximum of 8x8"));
+    assert!(run(&["--max-size", "16"], &input, &output).status.success());
+}
+
+#[test]
+fn writes_rust_consts() {
+    let input = temp_dir("rust_input");
+    let output = temp_dir("rust_output");
+    RgbaImage::from_pixel(2, 1, Rgba([1, 2, 3, 4]))
+        .save(input.join("Floor Wood.png"))
+        .unwrap();
+    assert!(run(&["--format", "rust", "--mipmaps"], &input, &output)
+        .status
+        .success());
+
+    let source = fs::read_to_string(output.join("texture_consts.rs")).unwrap();
+    assert!(source.contains("pub const FLOOR_WOOD: (usize, usize, [u8; 8]) = (2, 1, [1, 2, 3, 4, 1, 2, 3, 4]);"));
+    assert!(source.contains("pub const FLOOR_WOOD_MIP_1: (usize, usize, [u8; 4]) = (1, 1, "));
+}
+
+#[test]
+fn rejects_rust_const_names_starting_with_a_digit() {
+    let input = temp_dir("digit_input");
+    let output = temp_dir("digit_output");
+    RgbaImage::new(1, 1).save(input.join("1wall.png")).unwrap();
+    let result = run(&["--format", "rust"], &input, &output);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(stderr(&result).contains("\"1wall\" has to start with a letter"));
+    assert!(!output.join("texture_consts.rs").exists());
+
+    assert!(run(&[], &input, &output).status.success());
+}